@@ -1,11 +1,24 @@
+pub mod breakdown;
 pub mod calculations;
+pub mod charge;
+pub mod currency;
+pub mod date;
+pub mod discount;
+pub mod exemption;
 pub mod gratuity;
 pub mod items;
+pub mod minimum_spend;
+pub mod modifier;
+pub mod money;
+pub mod payment;
 pub mod quantity;
 pub mod receipt;
+pub mod reconcile;
+pub mod rounding;
 pub mod service_charge;
+pub mod solver;
+pub mod split;
 pub mod tax_group;
 pub mod tax;
-pub mod total;
 
 pub mod testing_ui_stuff;
\ No newline at end of file
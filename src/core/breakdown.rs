@@ -0,0 +1,264 @@
+use serde::Serialize;
+
+use crate::core::{
+    charge::{ChargeBase, ChargeValue},
+    currency::{Currency, CurrencyError, ExchangeRates, Locale, MoneyFormat},
+    discount::DiscountTiming,
+    gratuity::GratuityRule,
+    money::Money,
+    payment::Payment,
+    quantity::{Quantity, Unit},
+    tax::Tax,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaxAmount {
+    pub tax: Tax,
+    // What the tax was charged on
+    pub taxable: Money,
+    pub amount: Money,
+}
+
+// A line's share of a discount
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineDiscount {
+    pub discount_id: i64,
+    pub name: String,
+    pub timing: DiscountTiming,
+    pub amount: Money,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModifierBreakdown {
+    pub modifier_id: i64,
+    pub name: String,
+    pub unit_price: Money,
+    // Unit price times the parent line's count, weighed lines are charged once
+    pub extended_price: Money,
+    pub tax_inclusive: bool,
+    // Extended price less pre-tax discounts, and less tax if the price includes it
+    pub net_price: Money,
+    pub taxes: Vec<TaxAmount>,
+}
+
+impl ModifierBreakdown {
+    pub fn tax(&self) -> Money {
+        self.taxes.iter().map(|t| t.amount).sum()
+    }
+
+    pub fn included_tax(&self) -> Money {
+        if self.tax_inclusive { self.tax() } else { Money::ZERO }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LineBreakdown {
+    pub item_id: i64,
+    pub name: String,
+    pub quantity: Quantity,
+    pub unit_price: Money,
+    // What the unit price is charged per
+    pub unit: Unit,
+    // Unit price times quantity, not counting modifiers
+    pub extended_price: Money,
+    pub tax_inclusive: bool,
+    // Extended price less pre-tax discounts, and less tax if the price includes it
+    pub net_price: Money,
+    pub modifiers: Vec<ModifierBreakdown>,
+    // Discounts cover the item and its modifiers together
+    pub discounts: Vec<LineDiscount>,
+    // Taxes on the item itself, each modifier carries its own
+    pub taxes: Vec<TaxAmount>,
+}
+
+impl LineBreakdown {
+    // "1.25 lb @ $18.00/lb"
+    pub fn quantity_description(&self, format: &MoneyFormat) -> String {
+        self.quantity.describe_price(self.unit_price, self.unit, format)
+    }
+
+    // Extended price of the item and all of its modifiers
+    pub fn gross_price(&self) -> Money {
+        self.extended_price + self.modifiers.iter().map(|m| m.extended_price).sum::<Money>()
+    }
+
+    pub fn discount(&self) -> Money {
+        self.discounts.iter().map(|d| d.amount).sum()
+    }
+
+    pub fn pre_tax_discount(&self) -> Money {
+        self.discounts.iter()
+            .filter(|d| d.timing == DiscountTiming::PreTax)
+            .map(|d| d.amount)
+            .sum()
+    }
+
+    pub fn tax(&self) -> Money {
+        self.taxes.iter().map(|t| t.amount).sum::<Money>() + self.modifiers.iter().map(|m| m.tax()).sum::<Money>()
+    }
+
+    // Tax already inside the prices of the item and its modifiers
+    pub fn included_tax(&self) -> Money {
+        let item_tax = if self.tax_inclusive { self.taxes.iter().map(|t| t.amount).sum() } else { Money::ZERO };
+        item_tax + self.modifiers.iter().map(|m| m.included_tax()).sum::<Money>()
+    }
+
+    pub fn total(&self) -> Money {
+        self.gross_price() - self.discount() + self.tax() - self.included_tax()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChargeKind {
+    ServiceCharge,
+    Gratuity,
+    // What the receipt fell short of its minimum spend. Its base amount is the
+    // qualifying spend and its value the minimum.
+    MinimumSpend,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChargeBreakdown {
+    pub kind: ChargeKind,
+    pub id: i64,
+    pub name: String,
+    pub base: ChargeBase,
+    // What the base came to on this receipt, only percent charges are worked out on it
+    pub base_amount: Money,
+    pub value: ChargeValue,
+    pub amount: Money,
+    pub taxes: Vec<TaxAmount>,
+    // The rule that switched an automatic gratuity on
+    pub rule: Option<GratuityRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum RoundingReason {
+    // A tax rounded once for the whole receipt instead of line by line
+    InvoiceTax(Tax),
+    Cash,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoundingAdjustment {
+    pub reason: RoundingReason,
+    pub amount: Money,
+}
+
+/// Every figure that went into a receipt's total, and what has been paid on it.
+///
+/// The per-tax totals already include any rounding adjustments booked against
+/// them. Tax-inclusive prices carry their tax inside the subtotal, so
+/// `subtotal - discount + charges + tax - included_tax + cash rounding` is
+/// always `total`. Tips left on payments are among the charges.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReceiptBreakdown {
+    // Every amount below is in this currency
+    pub currency: Currency,
+    pub lines: Vec<LineBreakdown>,
+    // Extended prices of items and modifiers before any discount
+    pub subtotal: Money,
+    pub discount: Money,
+    pub charges: Vec<ChargeBreakdown>,
+    // One entry per tax, summed over lines and charges
+    pub taxes: Vec<TaxAmount>,
+    pub tax: Money,
+    // The part of `tax` that was already in tax-inclusive prices
+    pub included_tax: Money,
+    pub rounding: Vec<RoundingAdjustment>,
+    pub total: Money,
+    // Taxes the receipt's exemption kept off, as they would have been charged
+    pub exempted: Vec<TaxAmount>,
+    pub payments: Vec<Payment>,
+    // Every tender taken, tips included
+    pub paid: Money,
+    // What is still owed
+    pub balance: Money,
+    // What was paid over the total
+    pub overpayment: Money,
+    // The part of the overpayment handed back, it can't be more than the cash taken
+    pub change_due: Money,
+}
+
+impl ReceiptBreakdown {
+    pub fn charge_total(&self, kind: ChargeKind) -> Money {
+        self.charges.iter()
+            .filter(|charge| charge.kind == kind)
+            .map(|charge| charge.amount)
+            .sum()
+    }
+
+    pub fn service_charge(&self) -> Money {
+        self.charge_total(ChargeKind::ServiceCharge)
+    }
+
+    pub fn gratuity(&self) -> Money {
+        self.charge_total(ChargeKind::Gratuity)
+    }
+
+    pub fn shortfall(&self) -> Money {
+        self.charge_total(ChargeKind::MinimumSpend)
+    }
+
+    // The total in the home currency of `rates`
+    pub fn home_total(&self, rates: &ExchangeRates) -> Result<Money, CurrencyError> {
+        rates.convert(self.total, self.currency)
+    }
+
+    // The receipt as printed, one figure per line, in its own currency written the
+    // way `locale` writes money
+    pub fn render(&self, locale: Locale) -> String {
+        let format = &MoneyFormat::new(self.currency, locale);
+        let mut lines = Vec::new();
+        for line in &self.lines {
+            lines.push(format!("{}  {}  {}", line.name, line.quantity_description(format), format.format(line.extended_price)));
+            for modifier in &line.modifiers {
+                lines.push(format!("  {}  {}", modifier.name, format.format(modifier.extended_price)));
+            }
+            for discount in &line.discounts {
+                lines.push(format!("  {}  -{}", discount.name, format.format(discount.amount)));
+            }
+        }
+
+        lines.push(format!("Subtotal  {}", format.format(self.subtotal)));
+        if !self.discount.is_zero() {
+            lines.push(format!("Discount  -{}", format.format(self.discount)));
+        }
+        for charge in &self.charges {
+            lines.push(format!("{}  {}", charge.name, format.format(charge.amount)));
+        }
+        for tax in &self.taxes {
+            lines.push(format!("{}  {}", tax.tax.name, format.format(tax.amount)));
+        }
+        for adjustment in self.rounding.iter().filter(|a| a.reason == RoundingReason::Cash) {
+            lines.push(format!("Rounding  {}", format.format(adjustment.amount)));
+        }
+        lines.push(format!("Total  {}", format.format(self.total)));
+
+        for payment in &self.payments {
+            lines.push(format!("{}  {}", payment.tender, format.format(payment.tendered())));
+        }
+        if !self.payments.is_empty() {
+            lines.push(format!("Balance  {}", format.format(self.balance)));
+        }
+        if !self.change_due.is_zero() {
+            lines.push(format!("Change  {}", format.format(self.change_due)));
+        }
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::currency::{Currency, Locale};
+    use crate::core::receipt::Receipt;
+
+    #[test]
+    fn renders_in_the_receipts_currency() {
+        let mut receipt = Receipt::default();
+        receipt.set_currency(Currency::Gbp);
+
+        assert!(receipt.calculate_total().render(Locale::EnUs).ends_with("Total  £0.00"));
+    }
+}
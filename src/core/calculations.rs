@@ -1,3 +1,21 @@
+use crate::core::money::{Money, Rate};
+
+
+pub fn validate_i32(input: &str) -> bool {
+    input.parse::<i32>().is_ok()
+}
+
+pub fn convert_to_i32(input: &str) -> i32 {
+    input.parse::<i32>().unwrap_or(0)
+}
+
+pub fn validate_u32(input: &str) -> bool {
+    input.parse::<u32>().is_ok()
+}
+
+pub fn convert_to_u32(input: &str) -> u32 {
+    input.parse::<u32>().unwrap_or(0)
+}
 
 pub fn validate_i64(input: &str) -> bool {
     input.parse::<i64>().is_ok()
@@ -7,10 +25,19 @@ pub fn convert_to_i64(input: &str) -> i64 {
     input.parse::<i64>().unwrap_or(0)
 }
 
-pub fn validate_f64(input: &str) -> bool {
-    input.parse::<f64>().is_ok()
+pub fn validate_money(input: &str) -> bool {
+    input.parse::<Money>().is_ok()
+}
+
+pub fn convert_to_money(input: &str) -> Money {
+    input.parse::<Money>().unwrap_or_default()
+}
+
+// Rates are always typed in as a percent, "7.75" is 7.75%
+pub fn validate_percent(input: &str) -> bool {
+    input.parse::<Rate>().is_ok()
 }
 
-pub fn convert_to_f64(input: &str) -> f64 {
-    input.parse::<f64>().unwrap_or(0.0)
+pub fn convert_percent_to_rate(input: &str) -> Rate {
+    input.parse::<Rate>().unwrap_or_default()
 }
\ No newline at end of file
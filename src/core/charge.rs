@@ -0,0 +1,109 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::core::currency::MoneyFormat;
+use crate::core::money::{Money, ParseError, Rate, RoundingMode};
+
+// What a service charge or gratuity is worked out on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChargeBase {
+    // Item prices before tax
+    Subtotal,
+    // Item prices after discounts, plus their taxes
+    #[default]
+    TotalWithTax,
+    // Item prices before tax, less any discounts
+    DiscountedSubtotal,
+    // Item prices plus every tax and charge applied before this one
+    RunningTotal,
+}
+
+impl ChargeBase {
+    pub const ALL: [ChargeBase; 4] = [
+        ChargeBase::Subtotal,
+        ChargeBase::TotalWithTax,
+        ChargeBase::DiscountedSubtotal,
+        ChargeBase::RunningTotal,
+    ];
+}
+
+impl fmt::Display for ChargeBase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ChargeBase::Subtotal => "Subtotal",
+            ChargeBase::TotalWithTax => "Total with tax",
+            ChargeBase::DiscountedSubtotal => "Discounted subtotal",
+            ChargeBase::RunningTotal => "Running total",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// How much a service charge comes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChargeValue {
+    // A percent of the charge's base
+    Percent(Rate),
+    // A flat fee for the whole receipt
+    Fixed(Money),
+    // A fee for every guest on the receipt
+    PerGuest(Money),
+}
+
+impl ChargeValue {
+    pub fn amount(&self, base_amount: Money, guests: u32, mode: RoundingMode) -> Money {
+        match self {
+            ChargeValue::Percent(rate) => base_amount.apply_rate(*rate, mode),
+            ChargeValue::Fixed(amount) => *amount,
+            ChargeValue::PerGuest(amount) => *amount * guests as i64,
+        }
+    }
+
+    // "3.5%", "$50.00" or "$5.00/guest" written in the given currency
+    pub fn describe(&self, format: &MoneyFormat) -> String {
+        match self {
+            ChargeValue::Percent(rate) => rate.to_string(),
+            ChargeValue::Fixed(amount) => format.format(*amount),
+            ChargeValue::PerGuest(amount) => format!("{}/guest", format.format(*amount)),
+        }
+    }
+
+    // Reads back what `describe` writes, "3.5%" is a percent, "$5.00/guest" is per
+    // guest and any other amount is a flat fee
+    pub fn parse(input: &str, format: &MoneyFormat) -> Result<Self, ParseError> {
+        let input = input.trim();
+        if input.ends_with('%') {
+            input.parse::<Rate>().map(ChargeValue::Percent)
+        } else if let Some(amount) = input.strip_suffix("/guest") {
+            format.parse(amount).map(ChargeValue::PerGuest)
+        } else {
+            format.parse(input).map(ChargeValue::Fixed)
+        }
+    }
+}
+
+impl Default for ChargeValue {
+    fn default() -> Self {
+        ChargeValue::Percent(Rate::ZERO)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::currency::{Currency, Locale};
+
+    #[test]
+    fn edits_values_in_the_money_format() {
+        let format = MoneyFormat::new(Currency::Eur, Locale::FrFr);
+        for value in [
+            ChargeValue::Percent("3.5".parse().unwrap()),
+            ChargeValue::Fixed(Money::from_cents(5_000)),
+            ChargeValue::PerGuest(Money::from_cents(500)),
+        ] {
+            assert_eq!(ChargeValue::parse(&value.describe(&format), &format).unwrap(), value);
+        }
+        assert_eq!(ChargeValue::Fixed(Money::from_cents(5_000)).describe(&format), "50,00 €");
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+use crate::core::money::{parse_fixed, round_div, Money, ParseError, RoundingMode};
+
+// Exchange rates are kept to the millionth, 0.7312 is 731_200
+pub const EXCHANGE_RATE_PLACES: u32 = 6;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Usd,
+    Cad,
+    Eur,
+    Gbp,
+    Chf,
+}
+
+impl Currency {
+    pub const ALL: [Currency; 5] = [Currency::Usd, Currency::Cad, Currency::Eur, Currency::Gbp, Currency::Chf];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Cad => "CAD",
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+            Currency::Chf => "CHF",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::Usd | Currency::Cad => "$",
+            Currency::Eur => "€",
+            Currency::Gbp => "£",
+            Currency::Chf => "CHF",
+        }
+    }
+
+    // Digits after the decimal point, money is always held in these minor units
+    pub fn minor_units(&self) -> u32 {
+        match self {
+            Currency::Usd | Currency::Cad | Currency::Eur | Currency::Gbp | Currency::Chf => 2,
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = CurrencyError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Currency::ALL.iter()
+            .find(|currency| currency.code().eq_ignore_ascii_case(input.trim()))
+            .copied()
+            .ok_or_else(|| CurrencyError::UnknownCurrency(input.trim().to_string()))
+    }
+}
+
+// How a place writes amounts of money
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    // $1,234.56
+    #[default]
+    EnUs,
+    EnCa,
+    EnGb,
+    // 1 234,56 $
+    FrCa,
+    FrFr,
+    // 1.234,56 €
+    DeDe,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 6] = [Locale::EnUs, Locale::EnCa, Locale::EnGb, Locale::FrCa, Locale::FrFr, Locale::DeDe];
+
+    fn decimal_separator(&self) -> char {
+        match self {
+            Locale::EnUs | Locale::EnCa | Locale::EnGb => '.',
+            Locale::FrCa | Locale::FrFr | Locale::DeDe => ',',
+        }
+    }
+
+    fn group_separator(&self) -> char {
+        match self {
+            Locale::EnUs | Locale::EnCa | Locale::EnGb => ',',
+            Locale::FrCa | Locale::FrFr => ' ',
+            Locale::DeDe => '.',
+        }
+    }
+
+    fn symbol_after(&self) -> bool {
+        matches!(self, Locale::FrCa | Locale::FrFr | Locale::DeDe)
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Locale::EnUs => "en-US",
+            Locale::EnCa => "en-CA",
+            Locale::EnGb => "en-GB",
+            Locale::FrCa => "fr-CA",
+            Locale::FrFr => "fr-FR",
+            Locale::DeDe => "de-DE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Writes amounts of money in one currency the way one locale expects.
+///
+/// Every table and the receipt breakdown go through this, so an amount reads
+/// the same wherever it is shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MoneyFormat {
+    pub currency: Currency,
+    pub locale: Locale,
+}
+
+impl MoneyFormat {
+    pub fn new(currency: Currency, locale: Locale) -> Self {
+        MoneyFormat {
+            currency: currency,
+            locale: locale,
+        }
+    }
+
+    // The usual way of writing a currency where it is used
+    pub fn for_currency(currency: Currency) -> Self {
+        let locale = match currency {
+            Currency::Usd => Locale::EnUs,
+            Currency::Cad => Locale::EnCa,
+            Currency::Gbp => Locale::EnGb,
+            Currency::Eur | Currency::Chf => Locale::DeDe,
+        };
+
+        MoneyFormat::new(currency, locale)
+    }
+
+    pub fn format(&self, amount: Money) -> String {
+        let places = self.currency.minor_units();
+        let scale = 10_u64.pow(places);
+        let minor = amount.cents().unsigned_abs();
+
+        let mut number = group_digits(minor / scale, self.locale.group_separator());
+        if places > 0 {
+            number.push(self.locale.decimal_separator());
+            number.push_str(&format!("{:0width$}", minor % scale, width = places as usize));
+        }
+
+        let sign = if amount.is_negative() { "-" } else { "" };
+        if self.locale.symbol_after() {
+            format!("{}{} {}", sign, number, self.currency.symbol())
+        } else {
+            format!("{}{}{}", sign, self.currency.symbol(), number)
+        }
+    }
+
+    // Reads an amount written the way `format` writes it, the symbol can be left off
+    pub fn parse(&self, input: &str) -> Result<Money, ParseError> {
+        let input = input.trim();
+        let (sign, input) = match input.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", input),
+        };
+        let symbol = self.currency.symbol();
+        let input = input.strip_prefix(symbol).or_else(|| input.strip_suffix(symbol)).unwrap_or(input);
+
+        let input = input.trim();
+        let (whole, fraction) = match input.split_once(self.locale.decimal_separator()) {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (input, None),
+        };
+
+        // A separator that isn't grouping thousands was probably meant as the decimal
+        // point, "12.50" in German is a mistake rather than 1.250
+        let mut groups = whole.split(self.locale.group_separator());
+        let leading = groups.next().unwrap_or_default();
+        let mut groups = groups.peekable();
+        if groups.peek().is_some() && (leading.is_empty() || leading.len() > 3 || groups.any(|group| group.len() != 3)) {
+            return Err(ParseError::Grouping);
+        }
+
+        let whole: String = whole.chars().filter(|c| *c != self.locale.group_separator()).collect();
+        match fraction {
+            Some(fraction) => format!("{}{}.{}", sign, whole, fraction).parse(),
+            None => format!("{}{}", sign, whole).parse(),
+        }
+    }
+}
+
+// 1234567 -> "1,234,567"
+fn group_digits(whole: u64, separator: char) -> String {
+    let digits = whole.to_string();
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CurrencyError {
+    #[error("{0} isn't a supported currency")]
+    UnknownCurrency(String),
+    #[error("no exchange rate from {0}")]
+    MissingRate(Currency),
+    #[error("the exchange rate for {currency} isn't a number: {rate}")]
+    BadRate { currency: Currency, rate: String },
+    #[error("couldn't read the exchange rates: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("the exchange rates file is malformed: {0}")]
+    Malformed(#[from] serde_json::Error),
+}
+
+/// Rates for converting into a home currency, loaded from a local file.
+///
+/// The file names the home currency and what one unit of every other currency
+/// is worth in it, as text so the rates are read exactly:
+///
+/// `{ "home": "USD", "rates": { "CAD": "0.7312", "EUR": "1.0845" } }`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExchangeRates {
+    pub home: Currency,
+    // Millionths of a home unit per unit of each currency
+    rates: BTreeMap<Currency, i64>,
+}
+
+impl ExchangeRates {
+    pub fn new(home: Currency) -> Self {
+        ExchangeRates {
+            home: home,
+            rates: BTreeMap::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, CurrencyError> {
+        ExchangeRates::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, CurrencyError> {
+        #[derive(Deserialize)]
+        struct RatesFile {
+            home: Currency,
+            rates: BTreeMap<Currency, String>,
+        }
+
+        let file: RatesFile = serde_json::from_str(json)?;
+        let mut exchange_rates = ExchangeRates::new(file.home);
+        for (currency, rate) in file.rates {
+            let millionths = parse_fixed(&rate, EXCHANGE_RATE_PLACES)
+                .map_err(|_| CurrencyError::BadRate { currency: currency, rate: rate.clone() })?;
+            exchange_rates.rates.insert(currency, millionths);
+        }
+
+        Ok(exchange_rates)
+    }
+
+    // An amount in `from` as home currency, rounded half up to the home minor unit
+    pub fn convert(&self, amount: Money, from: Currency) -> Result<Money, CurrencyError> {
+        if from == self.home {
+            return Ok(amount);
+        }
+
+        let rate = *self.rates.get(&from).ok_or(CurrencyError::MissingRate(from))?;
+        let numerator = amount.cents() as i128 * rate as i128 * 10_i128.pow(self.home.minor_units());
+        let denominator = 10_i128.pow(from.minor_units() + EXCHANGE_RATE_PLACES);
+        let converted = round_div(numerator, denominator, RoundingMode::HalfUp);
+
+        Ok(Money::from_cents(i64::try_from(converted).expect("money overflow")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_what_it_formats_in_every_locale() {
+        for currency in Currency::ALL {
+            for locale in Locale::ALL {
+                let format = MoneyFormat::new(currency, locale);
+                for cents in [0, 5, 123_456, -98_765] {
+                    let amount = Money::from_cents(cents);
+                    assert_eq!(format.parse(&format.format(amount)).unwrap(), amount, "{} {}", currency, locale);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parses_amounts_written_without_the_symbol() {
+        let format = MoneyFormat::new(Currency::Eur, Locale::DeDe);
+        assert_eq!(format.parse("1.234,50").unwrap(), Money::from_cents(123_450));
+        assert_eq!(format.parse("5").unwrap(), Money::from_cents(500));
+        assert!(format.parse("£5").is_err());
+    }
+
+    #[test]
+    fn turns_down_a_decimal_point_taken_for_a_group_separator() {
+        let german = MoneyFormat::new(Currency::Eur, Locale::DeDe);
+        assert_eq!(german.parse("12.50"), Err(ParseError::Grouping));
+        assert_eq!(german.parse("1.234.5"), Err(ParseError::Grouping));
+        assert_eq!(german.parse("1.234.567"), Ok(Money::from_cents(123_456_700)));
+
+        let american = MoneyFormat::new(Currency::Usd, Locale::EnUs);
+        assert_eq!(american.parse("12,50"), Err(ParseError::Grouping));
+        assert!(american.parse("1.234,50").is_err());
+    }
+}
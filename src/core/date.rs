@@ -0,0 +1,167 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DateError {
+    #[error("dates are written as YYYY-MM-DD")]
+    Format,
+    #[error("{0} is not a day of the calendar")]
+    OutOfRange(String),
+}
+
+/// A calendar day, the time of day doesn't matter to anything here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Result<Self, DateError> {
+        if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+            return Err(DateError::OutOfRange(format!("{:04}-{:02}-{:02}", year, month, day)));
+        }
+
+        Ok(Date {
+            year: year,
+            month: month,
+            day: day,
+        })
+    }
+
+    // The local clock's day, in UTC
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs() as i64)
+            .unwrap_or(0);
+
+        Date::from_days(seconds.div_euclid(86_400))
+    }
+
+    pub fn next_day(&self) -> Self {
+        Date::from_days(self.days() + 1)
+    }
+
+    pub fn previous_day(&self) -> Self {
+        Date::from_days(self.days() - 1)
+    }
+
+    // Days since 1970-01-01
+    fn days(&self) -> i64 {
+        let year = if self.month <= 2 { self.year - 1 } else { self.year } as i64;
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = self.month as i64;
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+        let year = (year_of_era + era * 400) as i32 + if month <= 2 { 1 } else { 0 };
+
+        Date {
+            year: year,
+            month: month,
+            day: day,
+        }
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// "2024-07-01"
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = DateError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut parts = input.trim().splitn(3, '-');
+        let mut next = || parts.next().and_then(|part| part.parse::<u32>().ok()).ok_or(DateError::Format);
+        let (year, month, day) = (next()?, next()?, next()?);
+
+        Date::new(year as i32, month, day)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse::<Date>().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn steps_across_months_years_and_leap_days() {
+        assert_eq!(date("2024-01-31").next_day(), date("2024-02-01"));
+        assert_eq!(date("2024-02-28").next_day(), date("2024-02-29"));
+        assert_eq!(date("2023-02-28").next_day(), date("2023-03-01"));
+        assert_eq!(date("2024-12-31").next_day(), date("2025-01-01"));
+        assert_eq!(date("2024-03-01").previous_day(), date("2024-02-29"));
+        assert_eq!(date("2100-03-01").previous_day(), date("2100-02-28"));
+        assert_eq!(date("2000-03-01").previous_day(), date("2000-02-29"));
+        assert_eq!(date("2025-01-01").previous_day(), date("2024-12-31"));
+    }
+
+    #[test]
+    fn day_numbers_round_trip() {
+        assert_eq!(date("1970-01-01").days(), 0);
+        assert_eq!(date("1969-12-31").days(), -1);
+        assert_eq!(date("2000-03-01").days(), 11_017);
+
+        let mut day = date("1899-12-25");
+        for days in day.days()..date("2101-01-10").days() {
+            assert_eq!(Date::from_days(days), day);
+            assert_eq!(day.days(), days);
+            day = day.next_day();
+        }
+    }
+
+    #[test]
+    fn parses_and_writes_iso_dates() {
+        assert_eq!(date("2024-07-01"), Date::new(2024, 7, 1).unwrap());
+        assert_eq!(date(" 2024-7-1 ").to_string(), "2024-07-01");
+        assert_eq!("2023-02-29".parse::<Date>(), Err(DateError::OutOfRange("2023-02-29".to_string())));
+        assert_eq!("2024-13-01".parse::<Date>(), Err(DateError::OutOfRange("2024-13-01".to_string())));
+        assert_eq!("01/07/2024".parse::<Date>(), Err(DateError::Format));
+        assert_eq!(serde_json::to_string(&date("2024-07-01")).unwrap(), "\"2024-07-01\"");
+    }
+}
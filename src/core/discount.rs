@@ -0,0 +1,296 @@
+use std::fmt;
+
+use iced::widget::{button, column, row, text, text_input, Container};
+use iced::{Color, Element, Length};
+use serde::{Deserialize, Serialize};
+
+use crate::core::currency::MoneyFormat;
+use crate::core::items::Item;
+use crate::core::money::{Money, ParseError, Rate, RoundingMode};
+use crate::ui::custom_appearances::{pos_table_header, pos_table_row};
+use crate::ui::discount_list_view::{Message, DiscountEditState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiscountValue {
+    Percent(Rate),
+    Fixed(Money),
+}
+
+impl DiscountValue {
+    // "10%" or "$5.00" written in the given currency
+    pub fn describe(&self, format: &MoneyFormat) -> String {
+        match self {
+            DiscountValue::Percent(rate) => rate.to_string(),
+            DiscountValue::Fixed(amount) => format.format(*amount),
+        }
+    }
+
+    // Reads back what `describe` writes, "10%" is a percent off and anything else
+    // is an amount off
+    pub fn parse(input: &str, format: &MoneyFormat) -> Result<Self, ParseError> {
+        let input = input.trim();
+        if input.ends_with('%') {
+            input.parse::<Rate>().map(DiscountValue::Percent)
+        } else {
+            format.parse(input).map(DiscountValue::Fixed)
+        }
+    }
+}
+
+// What part of the receipt a discount comes off
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DiscountTarget {
+    // Index of the line in the receipt's order
+    Line(usize),
+    Category(String),
+    Receipt,
+}
+
+impl DiscountTarget {
+    pub fn applies_to(&self, index: usize, item: &Item) -> bool {
+        match self {
+            DiscountTarget::Line(line) => *line == index,
+            DiscountTarget::Category(category) => item.in_category(category),
+            DiscountTarget::Receipt => true,
+        }
+    }
+}
+
+impl fmt::Display for DiscountTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiscountTarget::Line(index) => write!(f, "Line {}", index + 1),
+            DiscountTarget::Category(category) => write!(f, "Category: {}", category),
+            DiscountTarget::Receipt => write!(f, "Receipt"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DiscountTiming {
+    // Comes off the price before it is taxed, so less tax is charged
+    #[default]
+    PreTax,
+    // Comes off what the guest owes after tax, the tax is unchanged
+    PostTax,
+}
+
+impl DiscountTiming {
+    pub const ALL: [DiscountTiming; 2] = [DiscountTiming::PreTax, DiscountTiming::PostTax];
+}
+
+impl fmt::Display for DiscountTiming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiscountTiming::PreTax => write!(f, "Pre-tax"),
+            DiscountTiming::PostTax => write!(f, "Post-tax"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Discount {
+    pub id: i64,
+    pub name: String,
+    pub value: DiscountValue,
+    pub target: DiscountTarget,
+    pub timing: DiscountTiming,
+}
+
+impl Discount {
+    pub fn new(id: i64, name: String, value: DiscountValue, target: DiscountTarget, timing: DiscountTiming) -> Self {
+        Discount {
+            id: id,
+            name: name,
+            value: value,
+            target: target,
+            timing: timing,
+        }
+    }
+}
+
+impl Discount {
+    // How much comes off `eligible`, never more than all of it
+    pub fn amount_off(&self, eligible: Money, rounding: RoundingMode) -> Money {
+        let amount = match self.value {
+            DiscountValue::Percent(rate) => eligible.apply_rate(rate, rounding),
+            DiscountValue::Fixed(amount) => amount,
+        };
+
+        amount.min(eligible).max(Money::ZERO)
+    }
+}
+
+impl Default for Discount {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            name: "Manager".to_string(),
+            value: DiscountValue::Percent(Rate::from_basis_points(1000)),
+            target: DiscountTarget::Receipt,
+            timing: DiscountTiming::PreTax,
+        }
+    }
+}
+
+
+pub fn create_discount_table(discounts: Vec<Discount>, format: MoneyFormat, edit_states: &mut std::collections::HashMap<i64, DiscountEditState>) -> Element<'static, Message> {
+    
+    let header = row![
+        table_cell("ID".to_string(), true, 75_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Name".to_string(), true, 150_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Amount".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Applies To".to_string(),true, 150_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Timing".to_string(),true, 100_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Actions".to_string(), true, 200_f32, 0, false, "".to_string(), edit_states),
+    ]
+    .spacing(1)
+    .padding(2)
+    .into(); 
+
+    // Table rows
+    let rows: Element<Message> = column(
+        std::iter::once(header)
+            .chain(
+                discounts.into_iter().map(|discount| {
+                    let is_editing = edit_states.get(&discount.id)
+                        .map( |state| state.is_editing)
+                        .unwrap_or(false);
+
+                    row![
+                    table_cell(discount.id.to_string(), false, 75_f32, discount.id, false, "id".to_string(), edit_states),
+                    table_cell(discount.name.clone(), false, 150_f32, discount.id, is_editing, "name".to_string(), edit_states),
+                    table_cell(discount.value.describe(&format), false, 100_f32, discount.id, is_editing, "value".to_string(), edit_states),
+                    table_cell(discount.target.to_string(), false, 150_f32, 0, false, "target".to_string(), edit_states),
+                    table_cell(discount.timing.to_string(), false, 100_f32, 0, false, "timing".to_string(), edit_states),
+                    table_cell_with_action(
+                        discount.id, 
+                        210_f32,
+                        is_editing)
+                    ].spacing(1)
+                    .padding(2)
+                    .into()
+                })
+            )
+            .collect::<Vec<_>>(),
+    )
+    .into();
+
+    Container::new(rows)
+        .width(Length::Fill)
+        .height(Length::Shrink)
+        .into()
+}
+
+// Helper function for standard table cells
+pub fn table_cell<'a>(
+    content: String, 
+    is_header: bool, 
+    width: f32,
+    discount_id: i64,
+    is_editing: bool,
+    field_name: String,
+    edit_states: &std::collections::HashMap<i64, DiscountEditState>,
+) -> Element<'a, Message> {
+    
+    if is_header{
+
+        let text_element = text(content.to_string())
+        .color(Color::BLACK)
+        .size(14)
+        .width(Length::Fixed(width))
+        .align_x(iced::Alignment::Center)
+        .align_y(iced::Alignment::Center);
+
+        Container::new(text_element)
+        .padding(5)
+        .style(pos_table_header)
+        .align_x(iced::Alignment::Center)
+        .align_y(iced::Alignment::Center)
+        .into()
+
+    } else {
+        let element: Element<Message> = if is_editing {
+
+            let current_value = if let Some(edit_state) = edit_states.get(&discount_id) {
+                match field_name.as_str() {
+                    "name" => edit_state.name.clone(),
+                    "value" => edit_state.value.clone(),
+                    _ => content.clone()
+                }
+            } else {
+                content.clone()
+            };
+
+            text_input("", &current_value)
+                .on_input(move |new_value| {
+                    Message::EditField(discount_id, field_name.to_string(), new_value)
+            }).size(12)
+            .width(Length::Fixed(width))
+            .into()
+        } else {
+            text(content)
+                .color(Color::BLACK)
+                .size(12)
+                .width(Length::Fixed(width))
+                .align_x(iced::Alignment::Center)
+                .align_y(iced::Alignment::Center)
+                .into()
+        };
+
+        Container::new(element)
+            .padding(5)
+            .style(pos_table_row)
+            .align_x(iced::Alignment::Center)
+            .align_y(iced::Alignment::Center)
+            .into()
+    }
+}
+
+// Helper function for cells with actions
+pub fn table_cell_with_action(discount_id: i64, width: f32, is_editing: bool) -> Element<'static, Message> {
+    let edit_save_button = if is_editing {
+        button("Save")
+            .on_press(Message::SaveDiscount(discount_id))
+            .width(Length::Fill)
+            .padding(2)
+    } else {
+        button("Edit")
+            .on_press(Message::ToggleEditMode(discount_id, true))
+            .width(Length::Fill)
+            .padding(2)
+    };
+
+    let delete_cancel_button = if is_editing {
+        button("Cancel")
+            .on_press(Message::ToggleEditMode(discount_id, false))
+            .width(Length::Fill)
+            .padding(2)
+    } else {
+        button("Delete")
+            .on_press(Message::DeleteDiscount(discount_id))
+            .width(Length::Fill)
+            .padding(2)
+    };
+
+    row![edit_save_button, delete_cancel_button]
+        .spacing(1)
+        .width(Length::Fixed(width))
+        .align_y(iced::Alignment::Center)
+        .into()
+
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tax_group::TaxGroup;
+
+    #[test]
+    fn category_discounts_match_the_item_category_in_any_case() {
+        let item = Item::new(1, "Wings".to_string(), "Food".to_string(), Money::from_cents(1200), TaxGroup::default());
+
+        assert!(DiscountTarget::Category("food".to_string()).applies_to(0, &item));
+        assert!(DiscountTarget::Category("FOOD".to_string()).applies_to(0, &item));
+        assert!(!DiscountTarget::Category("Beverage".to_string()).applies_to(0, &item));
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::core::{
+    breakdown::TaxAmount,
+    date::Date,
+    money::Money,
+    receipt::Receipt,
+    tax_group::TaxGroup,
+};
+
+/// Which taxes in its tax group an item, modifier or charge doesn't pay.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum ExemptTaxes {
+    #[default]
+    None,
+    All,
+    // Ids of the taxes it is exempt from, the rest of the group is charged
+    Only(Vec<i64>),
+}
+
+impl ExemptTaxes {
+    pub fn from_ids(tax_ids: Vec<i64>) -> Self {
+        if tax_ids.is_empty() { ExemptTaxes::None } else { ExemptTaxes::Only(tax_ids) }
+    }
+
+    // Switches every tax on or off. A partial exemption is kept when switched on,
+    // so only the taxes it lists stay off.
+    pub fn with_taxed(&self, taxed: bool) -> ExemptTaxes {
+        match (taxed, self) {
+            (false, _) => ExemptTaxes::All,
+            (true, ExemptTaxes::All) => ExemptTaxes::None,
+            (true, exempt_taxes) => exempt_taxes.clone(),
+        }
+    }
+
+    // The part of `tax_group` that is still charged
+    pub fn apply(&self, tax_group: &TaxGroup) -> TaxGroup {
+        match self {
+            ExemptTaxes::None => tax_group.clone(),
+            ExemptTaxes::All => TaxGroup { taxes: Vec::new(), ..tax_group.clone() },
+            ExemptTaxes::Only(tax_ids) => tax_group.without(tax_ids),
+        }
+    }
+
+    // Names of the exempted taxes in `tax_group`, "City, County"
+    pub fn describe(&self, tax_group: &TaxGroup) -> String {
+        match self {
+            ExemptTaxes::None => "None".to_string(),
+            ExemptTaxes::All => "All".to_string(),
+            ExemptTaxes::Only(tax_ids) => tax_ids.iter()
+                .map(|tax_id| tax_group.taxes.iter()
+                    .find(|tax| tax.id == *tax_id)
+                    .map_or(format!("#{}", tax_id), |tax| tax.name.clone()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+// The old tax_overide flag, true meant no tax at all
+impl From<bool> for ExemptTaxes {
+    fn from(tax_overide: bool) -> Self {
+        if tax_overide { ExemptTaxes::All } else { ExemptTaxes::None }
+    }
+}
+
+// "all", "none", or tax ids "2, 3"
+impl FromStr for ExemptTaxes {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_lowercase().as_str() {
+            "" | "none" => Ok(ExemptTaxes::None),
+            "all" => Ok(ExemptTaxes::All),
+            ids => ids.split(',')
+                .map(|id| id.trim().parse::<i64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map(ExemptTaxes::from_ids),
+        }
+    }
+}
+
+impl fmt::Display for ExemptTaxes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExemptTaxes::None => write!(f, "none"),
+            ExemptTaxes::All => write!(f, "all"),
+            ExemptTaxes::Only(tax_ids) => {
+                write!(f, "{}", tax_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", "))
+            }
+        }
+    }
+}
+
+// Stored as `true` for every tax or a list of tax ids, so the old flag reads back as it was
+impl Serialize for ExemptTaxes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ExemptTaxes::None => serializer.serialize_bool(false),
+            ExemptTaxes::All => serializer.serialize_bool(true),
+            ExemptTaxes::Only(tax_ids) => tax_ids.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ExemptTaxes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawExemptTaxes {
+            Flag(bool),
+            Ids(Vec<i64>),
+        }
+
+        Ok(match RawExemptTaxes::deserialize(deserializer)? {
+            RawExemptTaxes::Flag(tax_overide) => ExemptTaxes::from(tax_overide),
+            RawExemptTaxes::Ids(tax_ids) => ExemptTaxes::from_ids(tax_ids),
+        })
+    }
+}
+
+/// A customer's exemption certificate and the taxes it covers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TaxExemption {
+    pub customer: String,
+    pub certificate: String,
+    // Ids of the exempted taxes, every other tax is still charged
+    pub tax_ids: Vec<i64>,
+}
+
+impl TaxExemption {
+    pub fn new(customer: String, certificate: String, tax_ids: Vec<i64>) -> Self {
+        TaxExemption {
+            customer: customer,
+            certificate: certificate,
+            tax_ids: tax_ids,
+        }
+    }
+
+    pub fn exempts(&self, tax_id: i64) -> bool {
+        self.tax_ids.contains(&tax_id)
+    }
+}
+
+// The tax one receipt didn't charge because of an exemption
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExemptionRecord {
+    pub receipt_id: i32,
+    pub date: Option<Date>,
+    pub customer: String,
+    pub certificate: String,
+    pub taxes: Vec<TaxAmount>,
+    pub total: Money,
+}
+
+// One record for every exempt receipt, in the order given
+pub fn exemption_report(receipts: &[Receipt]) -> Vec<ExemptionRecord> {
+    receipts.iter()
+        .filter_map(|receipt| {
+            let exemption = receipt.exemption()?;
+            let taxes = receipt.calculate_total().exempted;
+
+            Some(ExemptionRecord {
+                receipt_id: receipt.id(),
+                date: receipt.date(),
+                customer: exemption.customer.clone(),
+                certificate: exemption.certificate.clone(),
+                total: taxes.iter().map(|t| t.amount).sum(),
+                taxes: taxes,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switching_taxes_on_keeps_a_partial_exemption() {
+        let city = ExemptTaxes::Only(vec![2]);
+
+        assert_eq!(city.with_taxed(true), city);
+        assert_eq!(city.with_taxed(false), ExemptTaxes::All);
+        assert_eq!(ExemptTaxes::All.with_taxed(true), ExemptTaxes::None);
+        assert_eq!(ExemptTaxes::None.with_taxed(false), ExemptTaxes::All);
+    }
+
+    #[test]
+    fn old_flags_and_tax_lists_read_back() {
+        assert_eq!(serde_json::from_str::<ExemptTaxes>("true").unwrap(), ExemptTaxes::All);
+        assert_eq!(serde_json::from_str::<ExemptTaxes>("false").unwrap(), ExemptTaxes::None);
+        assert_eq!(serde_json::from_str::<ExemptTaxes>("[2, 3]").unwrap(), ExemptTaxes::Only(vec![2, 3]));
+        assert_eq!("2, 3".parse::<ExemptTaxes>().unwrap(), ExemptTaxes::Only(vec![2, 3]));
+    }
+}
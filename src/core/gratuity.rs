@@ -1,31 +1,54 @@
+use std::fmt;
+
 use iced::widget::{button, column, row, text, text_input, Container};
 use iced::{Color, Element, Length};
-use std::hash::{Hash, Hasher};
+use serde::{Deserialize, Serialize};
 
+use crate::core::charge::ChargeBase;
+use crate::core::currency::MoneyFormat;
+use crate::core::exemption::ExemptTaxes;
+use crate::core::money::{Money, Rate};
 use crate::core::tax_group::TaxGroup;
 use crate::ui::custom_appearances::{pos_table_header, pos_table_row};
 use crate::ui::gratuity_list_view::{Message, GratuityEditState};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Gratuity {
     pub id: i64,
     pub name: String,
-    pub percent: f64,
+    pub rate: Rate,
+    pub base: ChargeBase,
+    // Charges on a receipt are applied from the lowest order up
+    pub order: i32,
     pub tax_group: TaxGroup,
-    pub tax_overide: bool,
+    // Taxes in the group it doesn't pay
+    pub exempt_taxes: ExemptTaxes,
+    // Automatic gratuities are only charged when one of their rules matches,
+    // with no rules the gratuity is always charged
+    pub rules: Vec<GratuityRule>,
 }
 
 impl Gratuity {
-    pub fn new(id: i64, name: String, percent: f64, tax_group: TaxGroup, tax_overide: bool) -> Self {
-        let tax_as_percentage = percent * 100.0;
+    pub fn new(id: i64, name: String, rate: Rate, base: ChargeBase, order: i32, tax_group: TaxGroup, exempt_taxes: ExemptTaxes) -> Self {
         
         Gratuity {
             id: id,
             name: name,
-            percent: percent,
+            rate: rate,
+            base: base,
+            order: order,
             tax_group: tax_group,
-            tax_overide: tax_overide,
+            exempt_taxes: exempt_taxes,
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn rules_to_string(&self, format: &MoneyFormat) -> String {
+        if self.rules.is_empty() {
+            return "Always".to_string();
         }
+
+        self.rules.iter().map(|rule| rule.describe(format)).collect::<Vec<_>>().join("; ")
     }
 }
 
@@ -34,36 +57,110 @@ impl Default for Gratuity {
         Self {
             id: 1,
             name: "Banquet".to_string(),
-            percent: 15.5,
+            rate: Rate::from_basis_points(1550),
+            base: ChargeBase::default(),
+            order: 0,
             tax_group: TaxGroup::default(),
-            tax_overide: false,
+            exempt_taxes: ExemptTaxes::None,
+            rules: Vec::new(),
         }
     }
 }
 
-impl Eq for Gratuity {}
+// What switches an automatic gratuity on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GratuityCondition {
+    // The receipt is for at least this many guests
+    PartySize(u32),
+    // The subtotal comes to at least this much
+    Subtotal(Money),
+}
+
+// Whether a rule looks at the subtotal before or after discounts, the gratuity
+// is then charged on that same subtotal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RuleBase {
+    #[default]
+    PreDiscount,
+    PostDiscount,
+}
+
+impl RuleBase {
+    pub const ALL: [RuleBase; 2] = [RuleBase::PreDiscount, RuleBase::PostDiscount];
+
+    pub fn charge_base(&self) -> ChargeBase {
+        match self {
+            RuleBase::PreDiscount => ChargeBase::Subtotal,
+            RuleBase::PostDiscount => ChargeBase::DiscountedSubtotal,
+        }
+    }
+}
 
-impl Hash for Gratuity {
-    fn hash<H: Hasher>(&self, state: &mut H){
-        self.id.hash(state);
-        self.name.hash(state);
-        self.tax_group.hash(state);
-        self.tax_overide.hash(state);
+impl fmt::Display for RuleBase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleBase::PreDiscount => write!(f, "Before discounts"),
+            RuleBase::PostDiscount => write!(f, "After discounts"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GratuityRule {
+    pub condition: GratuityCondition,
+    pub base: RuleBase,
+}
+
+impl GratuityRule {
+    pub fn new(condition: GratuityCondition, base: RuleBase) -> Self {
+        GratuityRule {
+            condition: condition,
+            base: base,
+        }
+    }
+
+    pub fn matches(&self, guests: u32, pre_discount: Money, post_discount: Money) -> bool {
+        let subtotal = match self.base {
+            RuleBase::PreDiscount => pre_discount,
+            RuleBase::PostDiscount => post_discount,
+        };
+
+        match self.condition {
+            GratuityCondition::PartySize(party_size) => guests >= party_size,
+            GratuityCondition::Subtotal(threshold) => subtotal >= threshold,
+        }
+    }
+}
+
+impl GratuityRule {
+    // "8+ guests, before discounts" or "Subtotal $500.00+, after discounts"
+    pub fn describe(&self, format: &MoneyFormat) -> String {
+        let condition = match self.condition {
+            GratuityCondition::PartySize(party_size) => format!("{}+ guests", party_size),
+            GratuityCondition::Subtotal(threshold) => format!("Subtotal {}+", format.format(threshold)),
+        };
+        format!("{}, {}", condition, self.base.to_string().to_lowercase())
+    }
+}
 
-        let percent_bits = self.percent.to_bits();
-        percent_bits.hash(state);
+impl fmt::Display for GratuityRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.describe(&MoneyFormat::default()))
     }
 }
 
 
-pub fn create_gratuity_table(gratuities: Vec<Gratuity>, edit_states: &mut std::collections::HashMap<i64, GratuityEditState>) -> Element<'static, Message> {
+pub fn create_gratuity_table(gratuities: Vec<Gratuity>, format: MoneyFormat, edit_states: &mut std::collections::HashMap<i64, GratuityEditState>) -> Element<'static, Message> {
     
     let header = row![
         table_cell("ID".to_string(), true, 75_f32, 0, false, "".to_string(), edit_states),
         table_cell("Name".to_string(), true, 150_f32, 0, false, "".to_string(), edit_states),
         table_cell("Percent".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Base".to_string(), true, 150_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Order".to_string(), true, 75_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Applies".to_string(), true, 200_f32, 0, false, "".to_string(), edit_states),
         table_cell("Tax Group".to_string(),true, 150_f32, 0, false, "".to_string(), edit_states),
-        table_cell("Taxed".to_string(),true, 75_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Exempt From".to_string(),true, 100_f32, 0, false, "".to_string(), edit_states),
         table_cell("Actions".to_string(), true, 200_f32, 0, false, "".to_string(), edit_states),
     ]
     .spacing(1)
@@ -82,9 +179,12 @@ pub fn create_gratuity_table(gratuities: Vec<Gratuity>, edit_states: &mut std::c
                     row![
                     table_cell(gratuity.id.to_string(), false, 75_f32, gratuity.id, false, "id".to_string(), edit_states),
                     table_cell(gratuity.name.clone(), false, 150_f32, gratuity.id, is_editing, "name".to_string(), edit_states),
-                    table_cell(format!("${:.2}", gratuity.percent), false, 100_f32, gratuity.id, is_editing, "percent".to_string(), edit_states),
+                    table_cell(format!("{:.2}", gratuity.rate), false, 100_f32, gratuity.id, is_editing, "percent".to_string(), edit_states),
+                    table_cell(gratuity.base.to_string(), false, 150_f32, 0, false, "base".to_string(), edit_states),
+                    table_cell(gratuity.order.to_string(), false, 75_f32, gratuity.id, is_editing, "order".to_string(), edit_states),
+                    table_cell(gratuity.rules_to_string(&format), false, 200_f32, 0, false, "rules".to_string(), edit_states),
                     table_cell(gratuity.tax_group.name.clone(),false, 150_f32, 0, false, "tax_group".to_string(), edit_states),
-                    table_cell(gratuity.tax_overide.to_string(),false, 75_f32, 0, false, "tax_overide".to_string(), edit_states),
+                    table_cell(gratuity.exempt_taxes.describe(&gratuity.tax_group),false, 100_f32, gratuity.id, is_editing, "exempt_taxes".to_string(), edit_states),
                     table_cell_with_action(
                         gratuity.id, 
                         210_f32,
@@ -138,6 +238,8 @@ pub fn table_cell<'a>(
                 match field_name.as_str() {
                     "name" => edit_state.name.clone(),
                     "percent" => edit_state.percent.clone(),
+                    "order" => edit_state.order.clone(),
+                    "exempt_taxes" => edit_state.exempt_taxes.clone(),
                     _ => content.clone()
                 }
             } else {
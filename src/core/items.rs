@@ -1,32 +1,86 @@
 use iced::widget::{button, column, row, text, text_input, Container};
 use iced::{Color, Element, Length};
-use std::hash::{Hash, Hasher};
 
+use crate::core::modifier::Modifier;
+use crate::core::currency::MoneyFormat;
+use crate::core::exemption::ExemptTaxes;
+use crate::core::money::Money;
+use crate::core::quantity::{Quantity, Unit};
 use crate::core::tax_group::TaxGroup;
 use crate::ui::custom_appearances::{pos_table_header, pos_table_row};
 use crate::ui::item_list_view::{Message, ItemEditState};
 
-#[derive( Debug, Clone, PartialEq)]
+#[derive( Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Item {
     pub id: i64,
     pub name: String,
     pub category: String,
-    pub price: f64,
+    pub price: Money,
+    // What the price is charged per, each or by weight
+    pub unit: Unit,
     pub tax_group: TaxGroup,
-    pub tax_overide: bool
+    // Taxes in the group it doesn't pay
+    pub exempt_taxes: ExemptTaxes,
+    // The price already includes tax, also set by the tax group
+    pub tax_inclusive: bool,
+    // Add-ons picked for this item on a receipt line
+    pub modifiers: Vec<Modifier>,
 }
 
 impl Item {
-    pub fn new(id: i64, name: String, _category: String, price: f64, tax_group: TaxGroup, tax_overide: bool) -> Self {
+    // Sold each, paying every tax in its group on top of the price
+    pub fn new(id: i64, name: String, category: String, price: Money, tax_group: TaxGroup) -> Self {
         Item {
             id: id,
             name: name,
-            category: "Default".to_string(),
+            category: category,
             price: price,
+            unit: Unit::Each,
             tax_group: tax_group,
-            tax_overide: tax_overide,
+            exempt_taxes: ExemptTaxes::None,
+            tax_inclusive: false,
+            modifiers: Vec::new(),
         }
     }
+
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn with_exempt_taxes(mut self, exempt_taxes: ExemptTaxes) -> Self {
+        self.exempt_taxes = exempt_taxes;
+        self
+    }
+
+    pub fn with_tax_inclusive(mut self, tax_inclusive: bool) -> Self {
+        self.tax_inclusive = tax_inclusive;
+        self
+    }
+}
+
+impl Item {
+    pub fn is_tax_inclusive(&self) -> bool {
+        self.tax_inclusive || self.tax_group.tax_inclusive
+    }
+
+    // "$18.00/lb", or just "$19.99" for items sold each
+    pub fn price_per_unit(&self, format: &MoneyFormat) -> String {
+        match self.unit {
+            Unit::Each => format.format(self.price),
+            unit => format!("{}/{}", format.format(self.price), unit),
+        }
+    }
+
+    // Categories are matched without regard to case, "food" is "Food"
+    pub fn in_category(&self, category: &str) -> bool {
+        self.category.eq_ignore_ascii_case(category)
+    }
+
+    // A whole number of this item's unit
+    pub fn quantity(&self, count: i64) -> Quantity {
+        Quantity::whole(count, self.unit)
+    }
 }
 
 impl Default for Item {
@@ -35,29 +89,18 @@ impl Default for Item {
             id: 1,
             name: "Rusty Crab".to_string(),
             category: "Food".to_string(),
-            price: 19.99,
+            price: Money::from_cents(1999),
+            unit: Unit::Each,
             tax_group: TaxGroup::default(),
-            tax_overide: false,
+            exempt_taxes: ExemptTaxes::None,
+            tax_inclusive: false,
+            modifiers: Vec::new(),
         }
     }
 }
 
-impl Eq for Item {}
-
-impl Hash for Item {
-    fn hash<H: Hasher>(&self, state: &mut H){
-        self.id.hash(state);
-        self.name.hash(state);
-        self.tax_group.hash(state);
-        self.tax_overide.hash(state);
-        self.category.hash(state);
-
-        let price_bits = self.price.to_bits();
-        price_bits.hash(state);
-    }
-}
 
-pub fn create_items_table(items: Vec<Item>, edit_states: &mut std::collections::HashMap<i64, ItemEditState>) -> Element<'static, Message> {
+pub fn create_items_table(items: Vec<Item>, format: MoneyFormat, edit_states: &mut std::collections::HashMap<i64, ItemEditState>) -> Element<'static, Message> {
     
     let header = row![
         table_cell("ID".to_string(), true, 75_f32, 0, false, "".to_string(), edit_states),
@@ -65,7 +108,8 @@ pub fn create_items_table(items: Vec<Item>, edit_states: &mut std::collections::
         table_cell("Price".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
         table_cell("Category".to_string(),true, 150_f32, 0, false, "".to_string(), edit_states),
         table_cell("Tax Group".to_string(),true, 150_f32, 0, false, "".to_string(), edit_states),
-        table_cell("Taxed".to_string(),true, 75_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Exempt From".to_string(),true, 100_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Inclusive".to_string(),true, 75_f32, 0, false, "".to_string(), edit_states),
         table_cell("Actions".to_string(), true, 200_f32, 0, false, "".to_string(), edit_states),
     ]
     .spacing(1)
@@ -84,10 +128,11 @@ pub fn create_items_table(items: Vec<Item>, edit_states: &mut std::collections::
                     row![
                     table_cell(item.id.to_string(), false, 75_f32, item.id, false, "id".to_string(), edit_states),
                     table_cell(item.name.clone(), false, 150_f32, item.id, is_editing, "name".to_string(), edit_states),
-                    table_cell(format!("${:.2}", item.price), false, 100_f32, item.id, is_editing, "price".to_string(), edit_states),
-                    table_cell(item.category.clone(), false, 150_f32, 0, false, "category".to_string(), edit_states),
+                    table_cell(item.price_per_unit(&format), false, 100_f32, item.id, is_editing, "price".to_string(), edit_states),
+                    table_cell(item.category.clone(), false, 150_f32, item.id, is_editing, "category".to_string(), edit_states),
                     table_cell(item.tax_group.name.clone(),false, 150_f32, 0, false, "tax_group".to_string(), edit_states),
-                    table_cell(item.tax_overide.to_string(),false, 75_f32, 0, false, "tax_overide".to_string(), edit_states),
+                    table_cell(item.exempt_taxes.describe(&item.tax_group),false, 100_f32, item.id, is_editing, "exempt_taxes".to_string(), edit_states),
+                    table_cell(item.is_tax_inclusive().to_string(),false, 75_f32, 0, false, "tax_inclusive".to_string(), edit_states),
                     table_cell_with_action(
                         item.id, 
                         210_f32,
@@ -141,6 +186,7 @@ pub fn table_cell<'a>(
                 match field_name.as_str() {
                     "name" => edit_state.name.clone(),
                     "price" => edit_state.price.clone(),
+                    "exempt_taxes" => edit_state.exempt_taxes.clone(),
                     _ => content.clone()
                 }
             } else {
@@ -0,0 +1,41 @@
+use crate::core::breakdown::LineBreakdown;
+use crate::core::items::Item;
+use crate::core::money::Money;
+use crate::core::tax_group::TaxGroup;
+
+/// A food and beverage minimum, the venue bills whatever the receipt falls short of it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MinimumSpend {
+    // Name of the shortfall line, "F&B Minimum" or "Room Fee"
+    pub name: String,
+    pub minimum: Money,
+    // Item categories that count towards the minimum, with none every item counts
+    pub categories: Vec<String>,
+    // Taxes on the shortfall, a room fee usually has none
+    pub tax_group: TaxGroup,
+}
+
+impl MinimumSpend {
+    pub fn new(name: String, minimum: Money, categories: Vec<String>, tax_group: TaxGroup) -> Self {
+        MinimumSpend {
+            name: name,
+            minimum: minimum,
+            categories: categories,
+            tax_group: tax_group,
+        }
+    }
+
+    pub fn counts(&self, item: &Item) -> bool {
+        self.categories.is_empty() || self.categories.iter().any(|category| item.in_category(category))
+    }
+
+    // What a line puts towards the minimum, its price and modifiers after pre-tax
+    // discounts, without tax
+    pub fn spend(line: &LineBreakdown) -> Money {
+        line.net_price + line.modifiers.iter().map(|m| m.net_price).sum::<Money>()
+    }
+
+    pub fn shortfall(&self, spend: Money) -> Money {
+        (self.minimum - spend).max(Money::ZERO)
+    }
+}
@@ -0,0 +1,39 @@
+use crate::core::money::Money;
+use crate::core::exemption::ExemptTaxes;
+use crate::core::tax_group::TaxGroup;
+
+// An add-on to an item on a receipt line, "add bacon +$2.00"
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Modifier {
+    pub id: i64,
+    pub name: String,
+    // Charged once for every unit of the parent item
+    pub price: Money,
+    pub tax_group: TaxGroup,
+    // Taxes in the group it doesn't pay
+    pub exempt_taxes: ExemptTaxes,
+}
+
+impl Modifier {
+    pub fn new(id: i64, name: String, price: Money, tax_group: TaxGroup, exempt_taxes: ExemptTaxes) -> Self {
+        Modifier {
+            id: id,
+            name: name,
+            price: price,
+            tax_group: tax_group,
+            exempt_taxes: exempt_taxes,
+        }
+    }
+}
+
+impl Default for Modifier {
+    fn default() -> Self {
+        Self {
+            id: 1,
+            name: "Add Bacon".to_string(),
+            price: Money::from_cents(200),
+            tax_group: TaxGroup::default(),
+            exempt_taxes: ExemptTaxes::None,
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Number of decimal places kept by each fixed-point type
+pub const MONEY_PLACES: u32 = 2;
+pub const PERCENT_PLACES: u32 = 4;
+
+// Rates are stored as millionths of the amount they apply to, 7.75% is 77_500
+pub const RATE_SCALE: i64 = 1_000_000;
+pub const BASIS_POINT: i64 = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RoundingMode {
+    // Halves round away from zero, 0.125 -> 0.13
+    #[default]
+    HalfUp,
+    // Halves round to the nearest even cent, 0.125 -> 0.12
+    HalfEven,
+}
+
+impl RoundingMode {
+    pub const ALL: [RoundingMode; 2] = [RoundingMode::HalfUp, RoundingMode::HalfEven];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    #[error("not a number")]
+    Invalid,
+    #[error("too many decimal places")]
+    Precision,
+    #[error("number is too large")]
+    Overflow,
+    #[error("digit groups have to be three digits long")]
+    Grouping,
+}
+
+/// An exact amount of money, stored as a whole number of cents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_cents(cents: i64) -> Self {
+        Money(cents)
+    }
+
+    pub fn cents(&self) -> i64 {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+
+    pub fn abs(&self) -> Money {
+        Money(self.0.abs())
+    }
+
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
+    }
+
+    pub fn checked_mul(self, factor: i64) -> Option<Money> {
+        self.0.checked_mul(factor).map(Money)
+    }
+
+    // Unrounded result of applying a rate, in millionths of a cent
+    pub fn apply_rate_exact(self, rate: Rate) -> i128 {
+        self.0 as i128 * rate.0 as i128
+    }
+
+    // Round an unrounded amount from apply_rate_exact to the cent
+    pub fn round_exact(exact: i128, mode: RoundingMode) -> Option<Money> {
+        i64::try_from(round_div(exact, RATE_SCALE as i128, mode)).ok().map(Money)
+    }
+
+    pub fn checked_apply_rate(self, rate: Rate, mode: RoundingMode) -> Option<Money> {
+        Money::round_exact(self.apply_rate_exact(rate), mode)
+    }
+
+    // Multiply by a rate and round the result to the cent
+    pub fn apply_rate(self, rate: Rate, mode: RoundingMode) -> Money {
+        self.checked_apply_rate(rate, mode).expect("money overflow")
+    }
+
+    /// Splits the amount in proportion to `weights`, the shares always add back up to it.
+    ///
+    /// Every share is first rounded toward zero, then the cents left over go one
+    /// at a time to the shares that lost the most, earliest first on a tie. With
+    /// no weight to go on the amount is split evenly.
+    pub fn allocate(self, weights: &[Money]) -> Vec<Money> {
+        if weights.is_empty() {
+            return Vec::new();
+        }
+
+        let total_weight: i128 = weights.iter().map(|weight| weight.0.max(0) as i128).sum();
+        let weights: Vec<i128> = if total_weight == 0 {
+            vec![1; weights.len()]
+        } else {
+            weights.iter().map(|weight| weight.0.max(0) as i128).collect()
+        };
+        let total_weight: i128 = weights.iter().sum();
+
+        let amount = self.0 as i128;
+        let mut shares: Vec<i64> = weights.iter().map(|weight| (amount * weight / total_weight) as i64).collect();
+        let mut remainders: Vec<(usize, i128)> = weights.iter().enumerate()
+            .map(|(index, weight)| (index, (amount * weight % total_weight).abs()))
+            .collect();
+        remainders.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let step = if amount < 0 { -1 } else { 1 };
+        let leftover = self.0 - shares.iter().sum::<i64>();
+        for (index, _) in remainders.iter().take(leftover.unsigned_abs() as usize) {
+            shares[*index] += step;
+        }
+
+        shares.into_iter().map(Money).collect()
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        self.checked_add(other).expect("money overflow")
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        self.checked_sub(other).expect("money overflow")
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<i64> for Money {
+    type Output = Money;
+
+    fn mul(self, factor: i64) -> Money {
+        self.checked_mul(factor).expect("money overflow")
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |acc, money| acc + money)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |acc, money| acc + *money)
+    }
+}
+
+impl FromStr for Money {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        parse_fixed(input, MONEY_PLACES).map(Money)
+    }
+}
+
+impl fmt::Display for RoundingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundingMode::HalfUp => write!(f, "Half up"),
+            RoundingMode::HalfEven => write!(f, "Half even"),
+        }
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, cents / 100, cents % 100)
+    }
+}
+
+/// A percentage rate, stored as millionths of the amount it is applied to.
+///
+/// Rates are only ever read and written as percents, so "7.75" is 7.75% and
+/// applying it to $100.00 gives $7.75.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rate(i64);
+
+impl Rate {
+    pub const ZERO: Rate = Rate(0);
+
+    pub const fn from_basis_points(basis_points: i64) -> Self {
+        Rate(basis_points * BASIS_POINT)
+    }
+
+    pub const fn from_millionths(millionths: i64) -> Self {
+        Rate(millionths)
+    }
+
+    pub fn millionths(&self) -> i64 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Rate) -> Option<Rate> {
+        self.0.checked_add(other.0).map(Rate)
+    }
+}
+
+impl Add for Rate {
+    type Output = Rate;
+
+    fn add(self, other: Rate) -> Rate {
+        self.checked_add(other).expect("rate overflow")
+    }
+}
+
+// Parses a percent such as "7.75" or "7.75%"
+impl FromStr for Rate {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        let input = input.strip_suffix('%').unwrap_or(input);
+        parse_fixed(input, PERCENT_PLACES).map(Rate)
+    }
+}
+
+// Displays the rate as a percent, "7.75%"
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent_scale = 10_u64.pow(PERCENT_PLACES);
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let scaled = self.0.unsigned_abs();
+        let whole = scaled / percent_scale;
+        let fraction = scaled % percent_scale;
+
+        // A precision sets the minimum number of decimal places, digits are never dropped
+        let digits = format!("{:0width$}", fraction, width = PERCENT_PLACES as usize);
+        let mut digits = digits.trim_end_matches('0').to_string();
+        while digits.len() < f.precision().unwrap_or(0) {
+            digits.push('0');
+        }
+
+        if digits.is_empty() {
+            write!(f, "{}{}%", sign, whole)
+        } else {
+            write!(f, "{}{}.{}%", sign, whole, digits)
+        }
+    }
+}
+
+// Rates are stored as percent text, "7.75%", so they survive a round trip exactly
+impl Serialize for Rate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawRate {
+            Text(String),
+            Number(f64),
+        }
+
+        // Older data stored the rate as a bare number. Those values were always
+        // entered and shown as percents, so 7.75 is read back as 7.75%
+        let text = match RawRate::deserialize(deserializer)? {
+            RawRate::Text(text) => text,
+            RawRate::Number(number) => number.to_string(),
+        };
+
+        text.parse::<Rate>().map_err(serde::de::Error::custom)
+    }
+}
+
+// Divide and round to the nearest whole number using the given mode
+pub fn round_div(numerator: i128, denominator: i128, mode: RoundingMode) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+
+    if remainder == 0 {
+        return quotient;
+    }
+
+    let away = if (numerator < 0) != (denominator < 0) { -1 } else { 1 };
+    let twice = (remainder * 2).abs();
+    let denominator = denominator.abs();
+
+    if twice > denominator {
+        quotient + away
+    } else if twice < denominator {
+        quotient
+    } else {
+        match mode {
+            RoundingMode::HalfUp => quotient + away,
+            RoundingMode::HalfEven => if quotient % 2 == 0 { quotient } else { quotient + away },
+        }
+    }
+}
+
+// Parse a plain decimal string into an integer scaled by 10^places
+pub(crate) fn parse_fixed(input: &str, places: u32) -> Result<i64, ParseError> {
+    let input = input.trim();
+    let (negative, digits) = match input.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, input),
+    };
+
+    let (whole, fraction) = match digits.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (digits, ""),
+    };
+
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(ParseError::Invalid);
+    }
+    if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(ParseError::Invalid);
+    }
+    if fraction.len() > places as usize {
+        return Err(ParseError::Precision);
+    }
+
+    let scale = 10_i64.pow(places);
+    let whole_value = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<i64>().map_err(|_| ParseError::Overflow)?
+    };
+    let fraction_value = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<i64>().map_err(|_| ParseError::Overflow)? * 10_i64.pow(places - fraction.len() as u32)
+    };
+
+    let value = whole_value
+        .checked_mul(scale)
+        .and_then(|value| value.checked_add(fraction_value))
+        .ok_or(ParseError::Overflow)?;
+
+    Ok(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fixed_point_decimals() {
+        assert_eq!(parse_fixed("12.34", 2), Ok(1234));
+        assert_eq!(parse_fixed("-0.5", 2), Ok(-50));
+        assert_eq!(parse_fixed(".75", 2), Ok(75));
+        assert_eq!(parse_fixed("7", 4), Ok(70_000));
+        assert_eq!(parse_fixed("1.234", 2), Err(ParseError::Precision));
+        assert_eq!(parse_fixed("1,5", 2), Err(ParseError::Invalid));
+        assert_eq!(parse_fixed("-", 2), Err(ParseError::Invalid));
+        assert_eq!(parse_fixed("99999999999999999999", 2), Err(ParseError::Overflow));
+    }
+
+    #[test]
+    fn rounds_halves_away_from_zero_or_to_even() {
+        assert_eq!(round_div(25, 10, RoundingMode::HalfUp), 3);
+        assert_eq!(round_div(-25, 10, RoundingMode::HalfUp), -3);
+        assert_eq!(round_div(25, 10, RoundingMode::HalfEven), 2);
+        assert_eq!(round_div(-25, 10, RoundingMode::HalfEven), -2);
+        assert_eq!(round_div(-35, 10, RoundingMode::HalfEven), -4);
+        assert_eq!(round_div(-26, 10, RoundingMode::HalfEven), -3);
+        assert_eq!(round_div(25, -10, RoundingMode::HalfEven), -2);
+    }
+
+    #[test]
+    fn allocates_every_cent_by_weight() {
+        let weights = [Money::from_cents(1), Money::from_cents(1), Money::from_cents(1)];
+        assert_eq!(Money::from_cents(100).allocate(&weights), vec![Money::from_cents(34), Money::from_cents(33), Money::from_cents(33)]);
+        assert_eq!(Money::from_cents(-100).allocate(&weights), vec![Money::from_cents(-34), Money::from_cents(-33), Money::from_cents(-33)]);
+
+        let weights = [Money::from_cents(700), Money::from_cents(300)];
+        assert_eq!(Money::from_cents(1_001).allocate(&weights), vec![Money::from_cents(701), Money::from_cents(300)]);
+
+        // Nothing to weigh by shares evenly
+        let weights = [Money::ZERO, Money::ZERO];
+        assert_eq!(Money::from_cents(5).allocate(&weights), vec![Money::from_cents(3), Money::from_cents(2)]);
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::core::money::Money;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Tender {
+    #[default]
+    Cash,
+    Card,
+    GiftCard,
+    // Billed to a hotel guest's room folio
+    RoomCharge,
+}
+
+impl Tender {
+    pub const ALL: [Tender; 4] = [Tender::Cash, Tender::Card, Tender::GiftCard, Tender::RoomCharge];
+
+    // Only cash handed over can be given back as change
+    pub fn gives_change(&self) -> bool {
+        matches!(self, Tender::Cash)
+    }
+}
+
+impl fmt::Display for Tender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tender::Cash => write!(f, "Cash"),
+            Tender::Card => write!(f, "Card"),
+            Tender::GiftCard => write!(f, "Gift Card"),
+            Tender::RoomCharge => write!(f, "Room Charge"),
+        }
+    }
+}
+
+/// One tender put towards a receipt, a receipt can be paid in several.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Payment {
+    pub id: i64,
+    pub tender: Tender,
+    // What was put towards the receipt, not counting the tip
+    pub amount: Money,
+    // Tip added when paying, usually on a card. It is charged on top of the
+    // receipt as a gratuity.
+    pub tip: Money,
+}
+
+impl Payment {
+    pub fn new(id: i64, tender: Tender, amount: Money, tip: Money) -> Self {
+        Payment {
+            id: id,
+            tender: tender,
+            amount: amount,
+            tip: tip,
+        }
+    }
+
+    // Everything taken with this tender, tip included
+    pub fn tendered(&self) -> Money {
+        self.amount + self.tip
+    }
+}
@@ -1,15 +1,187 @@
 use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Default, Debug, Clone, Eq, PartialEq)]
-pub struct Quantity(pub i32);
+use crate::core::currency::MoneyFormat;
+use crate::core::money::{parse_fixed, round_div, Money, ParseError, RoundingMode};
+
+// Quantities are kept to the thousandth, 1.25 lb is 1_250
+pub const QUANTITY_PLACES: u32 = 3;
+pub const QUANTITY_SCALE: i64 = 1_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Unit {
+    #[default]
+    Each,
+    Pound,
+    Kilogram,
+    Ounce,
+    Portion,
+}
+
+impl Unit {
+    pub const ALL: [Unit; 5] = [Unit::Each, Unit::Pound, Unit::Kilogram, Unit::Ounce, Unit::Portion];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Unit::Each => "ea",
+            Unit::Pound => "lb",
+            Unit::Kilogram => "kg",
+            Unit::Ounce => "oz",
+            Unit::Portion => "portion",
+        }
+    }
+
+    // Exact weight of one unit in nanograms, counted units have none
+    fn nanograms(&self) -> Option<i128> {
+        match self {
+            Unit::Pound => Some(453_592_370_000),
+            Unit::Kilogram => Some(1_000_000_000_000),
+            Unit::Ounce => Some(28_349_523_125),
+            Unit::Each | Unit::Portion => None,
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl FromStr for Unit {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_lowercase().as_str() {
+            "" | "ea" | "each" => Ok(Unit::Each),
+            "lb" | "lbs" | "pound" | "pounds" => Ok(Unit::Pound),
+            "kg" | "kilogram" | "kilograms" => Ok(Unit::Kilogram),
+            "oz" | "ounce" | "ounces" => Ok(Unit::Ounce),
+            "portion" | "portions" => Ok(Unit::Portion),
+            _ => Err(ParseError::Invalid),
+        }
+    }
+}
+
+/// An exact decimal amount of something, kept to the thousandth, with its unit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Quantity {
+    amount: i64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub const fn from_thousandths(amount: i64, unit: Unit) -> Self {
+        Quantity {
+            amount: amount,
+            unit: unit,
+        }
+    }
+
+    // A whole number of units
+    pub const fn whole(count: i64, unit: Unit) -> Self {
+        Quantity::from_thousandths(count * QUANTITY_SCALE, unit)
+    }
+
+    pub const fn each(count: i64) -> Self {
+        Quantity::whole(count, Unit::Each)
+    }
+
+    pub fn thousandths(&self) -> i64 {
+        self.amount
+    }
+
+    // Price of this quantity when `price` is charged per `unit`, rounded to the cent
+    pub fn extend(&self, price: Money, unit: Unit, rounding: RoundingMode) -> Money {
+        let exact = match (self.unit.nanograms(), unit.nanograms()) {
+            (Some(from), Some(to)) => round_div(price.cents() as i128 * self.amount as i128 * from, to * QUANTITY_SCALE as i128, rounding),
+            _ => round_div(price.cents() as i128 * self.amount as i128, QUANTITY_SCALE as i128, rounding),
+        };
+
+        Money::from_cents(i64::try_from(exact).expect("money overflow"))
+    }
+
+    // How many times the modifiers on a line are charged, once per unit for counted
+    // items and once for the whole line when the item is weighed
+    pub fn modifier_count(&self) -> Quantity {
+        match self.unit.nanograms() {
+            Some(_) => Quantity::each(1),
+            None => *self,
+        }
+    }
+
+    // "1.25 lb @ $18.00/lb", or "2 @ $19.99" for items sold each
+    pub fn describe_price(&self, price: Money, unit: Unit, format: &MoneyFormat) -> String {
+        if unit == Unit::Each {
+            format!("{} @ {}", self, format.format(price))
+        } else {
+            format!("{} @ {}/{}", self, format.format(price), unit)
+        }
+    }
+}
 
 impl fmt::Display for Quantity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.amount < 0 { "-" } else { "" };
+        let scaled = self.amount.unsigned_abs();
+        let whole = scaled / QUANTITY_SCALE as u64;
+        let fraction = format!("{:0width$}", scaled % QUANTITY_SCALE as u64, width = QUANTITY_PLACES as usize);
+        let fraction = fraction.trim_end_matches('0');
+
+        let number = if fraction.is_empty() {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}.{}", sign, whole, fraction)
+        };
+
         if f.alternate() {
             // Use an alternate format if the `#` flag is present
-            write!(f, "Quantity: {}", self.0)
+            write!(f, "Quantity: ")?;
+        }
+
+        // Items sold each are just a count
+        if self.unit == Unit::Each {
+            write!(f, "{}", number)
         } else {
-            write!(f, "{}", self.0)
+            write!(f, "{} {}", number, self.unit)
+        }
+    }
+}
+
+// Parses "2", "0.75 portion" or "1.25 lb"
+impl FromStr for Quantity {
+    type Err = ParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        let split = input.find(|c: char| c.is_alphabetic()).unwrap_or(input.len());
+        let (number, unit) = input.split_at(split);
+
+        Ok(Quantity::from_thousandths(parse_fixed(number, QUANTITY_PLACES)?, unit.parse()?))
+    }
+}
+
+// Quantities are stored as text, "1.25 lb"
+impl Serialize for Quantity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Quantity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawQuantity {
+            Text(String),
+            Count(i64),
+        }
+
+        // Older data stored a bare whole count
+        match RawQuantity::deserialize(deserializer)? {
+            RawQuantity::Text(text) => text.parse::<Quantity>().map_err(serde::de::Error::custom),
+            RawQuantity::Count(count) => Ok(Quantity::each(count)),
         }
     }
-}
\ No newline at end of file
+}
@@ -1,108 +1,789 @@
 use crate::core::{
+    breakdown::{ChargeBreakdown, ChargeKind, LineBreakdown, LineDiscount, ModifierBreakdown, ReceiptBreakdown, RoundingAdjustment, RoundingReason, TaxAmount},
+    charge::{ChargeBase, ChargeValue},
+    currency::Currency,
+    date::Date,
+    discount::{Discount, DiscountTiming},
+    exemption::{ExemptTaxes, TaxExemption},
     items::Item,
-    quantity::Quantity,
+    minimum_spend::MinimumSpend,
+    modifier::Modifier,
+    money::{Money, RoundingMode},
+    payment::Payment,
+    quantity::{Quantity, Unit},
+    rounding::{RoundingLevel, RoundingPolicy, TaxRounding},
     service_charge::ServiceCharge,
-    gratuity::Gratuity,
-    total::Total,
+    tax::Tax,
+    tax_group::TaxGroup,
+    gratuity::{Gratuity, GratuityRule},
 };
 
 
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Receipt {
     id: i32,
     name: String,
     order: Vec<(Item, Quantity)>,
-    service_charge: ServiceCharge,
-    gratuity: Gratuity,
+    service_charges: Vec<ServiceCharge>,
+    gratuities: Vec<Gratuity>,
+    discounts: Vec<Discount>,
+    // How many people the receipt is for, per-guest charges are multiplied by it
+    guests: u32,
+    // Day of the sale, taxes are charged at the rates in force then. Without one
+    // the current rates are used.
+    date: Option<Date>,
+    // Taxes this customer doesn't pay
+    exemption: Option<TaxExemption>,
+    // Prices on the receipt are all in this currency
+    currency: Currency,
+    payments: Vec<Payment>,
+    // Banquet minimum, any shortfall is billed as its own charge
+    minimum_spend: Option<MinimumSpend>,
+    rounding: RoundingPolicy,
+}
+
+// A receipt is always for at least one guest, so per-guest charges are billed
+impl Default for Receipt {
+    fn default() -> Self {
+        Receipt::new(0, String::new(), Vec::new(), Vec::new())
+    }
 }
 
 impl Receipt {
-    pub fn new(id: i32, name: String, service_charge: ServiceCharge, gratuity: Gratuity) -> Self {
+    pub fn new(id: i32, name: String, service_charges: Vec<ServiceCharge>, gratuities: Vec<Gratuity>) -> Self {
     
         Receipt {
             id: id,
             name: name,
             order: Vec::new(),
-            service_charge: service_charge,
-            gratuity: gratuity,
+            service_charges: service_charges,
+            gratuities: gratuities,
+            discounts: Vec::new(),
+            guests: 1,
+            date: None,
+            exemption: None,
+            currency: Currency::default(),
+            payments: Vec::new(),
+            minimum_spend: None,
+            rounding: RoundingPolicy::default(),
         }
     }
     
-    fn add_item(self: &mut Self, item: Item, quantity: Quantity){
-        self.order.push((item.clone(), quantity.clone()));
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn add_item(self: &mut Self, item: Item, quantity: Quantity){
+        self.order.push((item, quantity));
+
+    }
+
+    pub fn add_service_charge(&mut self, service_charge: ServiceCharge) {
+        self.service_charges.push(service_charge);
+    }
+
+    pub fn add_discount(&mut self, discount: Discount) {
+        self.discounts.push(discount);
+    }
+
+    pub fn discounts(&self) -> &[Discount] {
+        &self.discounts
+    }
+
+    pub fn service_charges(&self) -> &[ServiceCharge] {
+        &self.service_charges
+    }
+
+    pub fn gratuities(&self) -> &[Gratuity] {
+        &self.gratuities
+    }
+
+    pub fn set_gratuity(&mut self, index: usize, gratuity: Gratuity) {
+        if let Some(existing) = self.gratuities.get_mut(index) {
+            *existing = gratuity;
+        }
+    }
+
+    pub fn add_modifier(&mut self, line: usize, modifier: Modifier) {
+        if let Some((item, _)) = self.order.get_mut(line) {
+            item.modifiers.push(modifier);
+        }
+    }
+
+    pub fn guests(&self) -> u32 {
+        self.guests
+    }
+
+    pub fn set_guests(&mut self, guests: u32) {
+        self.guests = guests;
+    }
+
+    pub fn date(&self) -> Option<Date> {
+        self.date
+    }
+
+    pub fn set_date(&mut self, date: Date) {
+        self.date = Some(date);
+    }
+
+    pub fn exemption(&self) -> Option<&TaxExemption> {
+        self.exemption.as_ref()
+    }
+
+    pub fn set_exemption(&mut self, exemption: TaxExemption) {
+        self.exemption = Some(exemption);
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn set_currency(&mut self, currency: Currency) {
+        self.currency = currency;
+    }
+
+    pub fn add_payment(&mut self, payment: Payment) {
+        self.payments.push(payment);
+    }
+
+    pub fn payments(&self) -> &[Payment] {
+        &self.payments
+    }
+
+    pub fn minimum_spend(&self) -> Option<&MinimumSpend> {
+        self.minimum_spend.as_ref()
+    }
+
+    pub fn set_minimum_spend(&mut self, minimum_spend: MinimumSpend) {
+        self.minimum_spend = Some(minimum_spend);
+    }
+
+    pub fn rounding_policy(&self) -> RoundingPolicy {
+        self.rounding
+    }
+
+    pub fn set_rounding_policy(&mut self, rounding: RoundingPolicy) {
+        self.rounding = rounding;
+    }
+
+    // A tax group's own rounding wins over the receipt's
+    pub fn tax_rounding(&self, tax_group: &TaxGroup) -> TaxRounding {
+        tax_group.rounding.unwrap_or(self.rounding.tax)
+    }
+
+    // Tax backed out of an inclusive price is settled on the line, rounding it
+    // again per invoice would move the price itself
+    fn price_rounding(&self, tax_group: &TaxGroup, tax_inclusive: bool) -> TaxRounding {
+        let rounding = self.tax_rounding(tax_group);
+        if tax_inclusive {
+            TaxRounding::new(rounding.mode, RoundingLevel::PerLine)
+        } else {
+            rounding
+        }
+    }
+
+    pub fn order(&self) -> &[(Item, Quantity)] {
+        &self.order
+    }
+
+    pub fn clear_order(&mut self) {
+        self.order.clear();
+    }
+
+    // Switches the line's modifiers along with the item, they are taxed as part of it
+    pub fn set_line_taxed(&mut self, index: usize, taxed: bool) {
+        if let Some((item, _)) = self.order.get_mut(index) {
+            item.exempt_taxes = item.exempt_taxes.with_taxed(taxed);
+            for modifier in &mut item.modifiers {
+                modifier.exempt_taxes = modifier.exempt_taxes.with_taxed(taxed);
+            }
+        }
+    }
+
+    pub fn set_service_charge_taxed(&mut self, index: usize, taxed: bool) {
+        if let Some(service_charge) = self.service_charges.get_mut(index) {
+            service_charge.exempt_taxes = service_charge.exempt_taxes.with_taxed(taxed);
+        }
+    }
+
+    pub fn set_gratuity_taxed(&mut self, index: usize, taxed: bool) {
+        if let Some(gratuity) = self.gratuities.get_mut(index) {
+            gratuity.exempt_taxes = gratuity.exempt_taxes.with_taxed(taxed);
+        }
+    }
+
+    // Charge `taxes` in place of every tax group's own. Whatever paid none of its
+    // taxes, through its own exemption or the customer's, pays none of the new ones
+    // and everything else pays all of them. Inclusive pricing and rounding stay.
+    pub fn replace_taxes(&mut self, taxes: &[Tax]) {
+        let exemption = self.exemption.take();
+        for (tax_group, exempt_taxes) in self.tax_parts_mut() {
+            let mut charged = exempt_taxes.as_deref().map_or(tax_group.clone(), |exempt_taxes| exempt_taxes.apply(tax_group));
+            if let Some(exemption) = &exemption {
+                charged = charged.without(&exemption.tax_ids);
+            }
+
+            tax_group.taxes = if charged.taxes.is_empty() { Vec::new() } else { taxes.to_vec() };
+            if let Some(exempt_taxes) = exempt_taxes {
+                *exempt_taxes = ExemptTaxes::None;
+            }
+        }
+    }
 
+    // Round the taxes of every tax group on the receipt the same way, None leaves
+    // them all to the receipt's rounding policy
+    pub fn set_group_rounding(&mut self, rounding: Option<TaxRounding>) {
+        for (group, _) in self.tax_parts_mut() {
+            group.rounding = rounding;
+        }
+    }
+
+    // Every tax group on the receipt, with the exemption of whatever is charged
+    // through it. The minimum spend has no exemption of its own.
+    fn tax_parts_mut(&mut self) -> Vec<(&mut TaxGroup, Option<&mut ExemptTaxes>)> {
+        let mut parts = Vec::new();
+        for (item, _) in &mut self.order {
+            parts.push((&mut item.tax_group, Some(&mut item.exempt_taxes)));
+            for modifier in &mut item.modifiers {
+                parts.push((&mut modifier.tax_group, Some(&mut modifier.exempt_taxes)));
+            }
+        }
+        for service_charge in &mut self.service_charges {
+            parts.push((&mut service_charge.tax_group, Some(&mut service_charge.exempt_taxes)));
+        }
+        for gratuity in &mut self.gratuities {
+            parts.push((&mut gratuity.tax_group, Some(&mut gratuity.exempt_taxes)));
+        }
+        if let Some(minimum_spend) = &mut self.minimum_spend {
+            parts.push((&mut minimum_spend.tax_group, None));
+        }
+        parts
     }
     
     fn print_receipt(self: Self){
         let calculation = self.calculate_total();
 
         for (item, quantity) in &self.order {
-            let cost = item.price * quantity.0 as f64;
+            let cost = self.extend(item.price, quantity, item.unit);
 
         }
 
     }
     
-    fn calculate_total(self: &Self) -> Total {
-        let mut total = 0.0;
-        let mut tax = 0.0;
-        let mut service = 0.0;
-        let mut grat = 0.0;
-        let mut subtotal = 0.0;
+    pub fn price_line(&self, item: &Item, quantity: &Quantity) -> LineBreakdown {
+        self.price_discounted_line(item, quantity, Vec::new())
+    }
 
-        for (item, quantity) in &self.order {
-            if item.tax_overide {
-                // Add price directly if tax is overridden
-                total += item.price * quantity.0 as f64;
-            } else {
-                // Calculate tax from the tax group
-                if let Some(tax_percent) = item.tax_group.get_tax_percent("Sales Tax") {
-                    let tax_amount = item.price * tax_percent;
-                    subtotal += item.price * quantity.0 as f64;
-                    total += (item.price + tax_amount) * quantity.0 as f64;
-                    tax += tax_amount * quantity.0 as f64;
+    // Price a line with its share of the pre-tax discounts, which come off before it is taxed
+    fn price_discounted_line(&self, item: &Item, quantity: &Quantity, discounts: Vec<LineDiscount>) -> LineBreakdown {
+        let mut line = LineBreakdown {
+            item_id: item.id,
+            name: item.name.clone(),
+            quantity: *quantity,
+            unit_price: item.price,
+            unit: item.unit,
+            extended_price: self.extend(item.price, quantity, item.unit),
+            tax_inclusive: item.is_tax_inclusive(),
+            net_price: Money::ZERO,
+            modifiers: item.modifiers.iter().map(|modifier| ModifierBreakdown {
+                modifier_id: modifier.id,
+                name: modifier.name.clone(),
+                unit_price: modifier.price,
+                extended_price: self.extend(modifier.price, &quantity.modifier_count(), Unit::Each),
+                tax_inclusive: item.is_tax_inclusive() || modifier.tax_group.tax_inclusive,
+                net_price: Money::ZERO,
+                taxes: Vec::new(),
+            }).collect(),
+            discounts: discounts,
+            taxes: Vec::new(),
+        };
 
-                } else {
+        // The pre-tax discount is shared between the item and its modifiers so each is taxed on its own part
+        let weights: Vec<Money> = std::iter::once(line.extended_price)
+            .chain(line.modifiers.iter().map(|m| m.extended_price))
+            .collect();
+        let shares = line.pre_tax_discount().allocate(&weights);
+
+        // Items and modifiers are only taxed by the part of their group they aren't exempt from
+        line.taxes = self.price_taxes(&item.exempt_taxes.apply(&item.tax_group), line.extended_price - shares[0], line.tax_inclusive);
+        line.net_price = line.extended_price - shares[0] - if line.tax_inclusive { line.taxes.iter().map(|t| t.amount).sum() } else { Money::ZERO };
+
+        for ((modifier, priced), share) in item.modifiers.iter().zip(&mut line.modifiers).zip(&shares[1..]) {
+            priced.taxes = self.price_taxes(&modifier.exempt_taxes.apply(&modifier.tax_group), priced.extended_price - *share, priced.tax_inclusive);
+            priced.net_price = priced.extended_price - *share - priced.included_tax();
+        }
+
+        line
+    }
+
+    // Price of a quantity at `price` per `unit`, rounded the same way as tax
+    fn extend(&self, price: Money, quantity: &Quantity, unit: Unit) -> Money {
+        quantity.extend(price, unit, self.rounding.tax.mode)
+    }
+
+    // A tax group as it applies to this sale, at the rates in force on the day
+    // and without the taxes the customer is exempt from
+    fn effective(&self, tax_group: &TaxGroup) -> TaxGroup {
+        let tax_group = match self.date {
+            Some(date) => tax_group.on(date),
+            None => tax_group.clone(),
+        };
+
+        match &self.exemption {
+            Some(exemption) => tax_group.without(&exemption.tax_ids),
+            None => tax_group,
+        }
+    }
+
+    // Taxes on top of a price, or backed out of it when the price includes them
+    fn price_taxes(&self, tax_group: &TaxGroup, price: Money, tax_inclusive: bool) -> Vec<TaxAmount> {
+        let mode = self.tax_rounding(tax_group).mode;
+        let tax_group = self.effective(tax_group);
+        if tax_inclusive {
+            tax_group.calculate_included_taxes(price, mode)
+        } else {
+            tax_group.calculate_taxes(price, mode)
+        }
+    }
+
+    // Split every discount with the given timing across the lines it applies to,
+    // in proportion to what is still left on each line
+    fn allocate_discounts(&self, timing: DiscountTiming, remaining: &mut [Money], discounts: &mut [Vec<LineDiscount>]) {
+        for discount in self.discounts.iter().filter(|discount| discount.timing == timing) {
+            let eligible: Vec<usize> = self.order.iter().enumerate()
+                .filter(|(index, (item, _))| discount.target.applies_to(*index, item))
+                .map(|(index, _)| index)
+                .collect();
+
+            let weights: Vec<Money> = eligible.iter().map(|index| remaining[*index]).collect();
+            let amount = discount.amount_off(weights.iter().sum(), self.rounding.tax.mode);
 
+            for (index, share) in eligible.iter().zip(amount.allocate(&weights)) {
+                if share.is_zero() {
+                    continue;
                 }
+
+                remaining[*index] -= share;
+                discounts[*index].push(LineDiscount {
+                    discount_id: discount.id,
+                    name: discount.name.clone(),
+                    timing: timing,
+                    amount: share,
+                });
+            }
+        }
+    }
+
+    pub fn calculate_total(self: &Self) -> ReceiptBreakdown {
+        let mut breakdown = self.price_receipt();
+
+        // What the exempt taxes would have come to without the exemption
+        if let Some(exemption) = &self.exemption {
+            let unexempted = Receipt { exemption: None, ..self.clone() };
+            breakdown.exempted = unexempted.price_receipt().taxes.into_iter()
+                .filter(|t| exemption.exempts(t.tax.id))
+                .collect();
+        }
+
+        breakdown
+    }
+
+    // Only the total, without pricing the receipt again for the exemption report
+    pub fn total(&self) -> Money {
+        self.price_receipt().total
+    }
+
+    fn price_receipt(&self) -> ReceiptBreakdown {
+        let mut subtotal = Money::ZERO;
+        let mut lines = Vec::new();
+        let mut ledger = TaxLedger::default();
+
+        let mut remaining: Vec<Money> = self.order.iter()
+            .map(|(item, quantity)| {
+                self.extend(item.price, quantity, item.unit)
+                    + item.modifiers.iter().map(|m| self.extend(m.price, &quantity.modifier_count(), Unit::Each)).sum::<Money>()
+            })
+            .collect();
+        let mut line_discounts = vec![Vec::new(); self.order.len()];
+        self.allocate_discounts(DiscountTiming::PreTax, &mut remaining, &mut line_discounts);
+
+        for ((item, quantity), discounts) in self.order.iter().zip(line_discounts) {
+            let line = self.price_discounted_line(item, quantity, discounts);
+
+            ledger.add(&line.taxes, self.price_rounding(&item.tax_group, line.tax_inclusive));
+            for (modifier, priced) in item.modifiers.iter().zip(&line.modifiers) {
+                ledger.add(&priced.taxes, self.price_rounding(&modifier.tax_group, priced.tax_inclusive));
+            }
+            subtotal += line.gross_price();
+
+            lines.push(line);
+        }
+
+        // Post-tax discounts are shared out by what each line comes to with its tax
+        let mut remaining: Vec<Money> = lines.iter().map(|line| line.total()).collect();
+        let mut line_discounts = vec![Vec::new(); lines.len()];
+        self.allocate_discounts(DiscountTiming::PostTax, &mut remaining, &mut line_discounts);
+        for (line, discounts) in lines.iter_mut().zip(line_discounts) {
+            line.discounts.extend(discounts);
+        }
+
+        let mode = self.rounding.tax.mode;
+        let included_tax: Money = lines.iter().map(|line| line.included_tax()).sum();
+        // Only tax that is added on top of the prices
+        let line_tax = ledger.total() - included_tax;
+        let discount: Money = lines.iter().map(|line| line.discount()).sum();
+        let discounted_subtotal = subtotal - discount;
+
+        let mut charges: Vec<ChargeBreakdown> = Vec::new();
+
+        // The minimum spend shortfall comes before every other charge
+        if let Some(minimum_spend) = &self.minimum_spend {
+            let spend: Money = self.order.iter().zip(&lines)
+                .filter(|((item, _), _)| minimum_spend.counts(item))
+                .map(|(_, line)| MinimumSpend::spend(line))
+                .sum();
+            let shortfall = minimum_spend.shortfall(spend);
+
+            if !shortfall.is_zero() {
+                let rounding = self.tax_rounding(&minimum_spend.tax_group);
+                let taxes = self.effective(&minimum_spend.tax_group).calculate_taxes(shortfall, rounding.mode);
+                ledger.add(&taxes, rounding);
+
+                charges.push(ChargeBreakdown {
+                    kind: ChargeKind::MinimumSpend,
+                    id: 0,
+                    name: minimum_spend.name.clone(),
+                    base: ChargeBase::default(),
+                    base_amount: spend,
+                    value: ChargeValue::Fixed(minimum_spend.minimum),
+                    amount: shortfall,
+                    taxes: taxes,
+                    rule: None,
+                });
             }
         }
-        
-        let service_charge_amount = self.service_charge.percent * total;
-        let gratuity_amount = self.gratuity.percent * total;
-        service = service_charge_amount.clone();
-        grat = gratuity_amount.clone();
-         
-        total += service_charge_amount;
-        total += gratuity_amount;
-        
-        Total {
+        for charge in self.charges_in_order() {
+            // Automatic gratuities are left off unless one of their rules matches, and are
+            // then charged on the subtotal the rule looked at
+            let rule = charge.rules.iter()
+                .find(|rule| rule.matches(self.guests, subtotal - included_tax, discounted_subtotal - included_tax))
+                .copied();
+            if !charge.rules.is_empty() && rule.is_none() {
+                continue;
+            }
+            let base = rule.map(|rule| rule.base.charge_base()).unwrap_or(charge.base);
+
+            let base_amount = match base {
+                ChargeBase::Subtotal => subtotal - included_tax,
+                ChargeBase::TotalWithTax => discounted_subtotal + line_tax,
+                ChargeBase::DiscountedSubtotal => discounted_subtotal - included_tax,
+                ChargeBase::RunningTotal => discounted_subtotal - included_tax + ledger.total() + charges.iter().map(|c| c.amount).sum::<Money>(),
+            };
+            let amount = charge.value.amount(base_amount, self.guests, mode);
+
+            // Charges are taxed through their own tax group, less any taxes they are exempt from
+            let rounding = self.tax_rounding(charge.tax_group);
+            let taxes = self.effective(&charge.exempt_taxes.apply(charge.tax_group)).calculate_taxes(amount, rounding.mode);
+            ledger.add(&taxes, rounding);
+
+            charges.push(ChargeBreakdown {
+                kind: charge.kind,
+                id: charge.id,
+                name: charge.name.to_string(),
+                base: base,
+                base_amount: base_amount,
+                value: charge.value,
+                amount: amount,
+                taxes: taxes,
+                rule: rule,
+            });
+        }
+
+        let (taxes, mut rounding) = ledger.finish();
+        let tax = taxes.iter().map(|t| t.amount).sum();
+        let charge_total: Money = charges.iter().map(|charge| charge.amount).sum();
+        let total = discounted_subtotal + tax - included_tax + charge_total;
+
+        let cash_total = self.rounding.cash.round(total);
+        if cash_total != total {
+            rounding.push(RoundingAdjustment {
+                reason: RoundingReason::Cash,
+                amount: cash_total - total,
+            });
+        }
+
+        // Tips left when paying go on top of the rounded total as untaxed gratuities
+        let tips: Vec<ChargeBreakdown> = self.payments.iter()
+            .filter(|payment| !payment.tip.is_zero())
+            .map(|payment| ChargeBreakdown {
+                kind: ChargeKind::Gratuity,
+                id: payment.id,
+                name: format!("{} Tip", payment.tender),
+                base: ChargeBase::default(),
+                base_amount: Money::ZERO,
+                value: ChargeValue::Fixed(payment.tip),
+                amount: payment.tip,
+                taxes: Vec::new(),
+                rule: None,
+            })
+            .collect();
+        let total = cash_total + tips.iter().map(|tip| tip.amount).sum::<Money>();
+        charges.extend(tips);
+
+        let paid: Money = self.payments.iter().map(|payment| payment.tendered()).sum();
+        let overpayment = (paid - total).max(Money::ZERO);
+        let cash: Money = self.payments.iter()
+            .filter(|payment| payment.tender.gives_change())
+            .map(|payment| payment.tendered())
+            .sum();
+
+        ReceiptBreakdown {
+            currency: self.currency,
+            lines: lines,
             subtotal: subtotal,
-            total: total,
+            discount: discount,
+            charges: charges,
+            taxes: taxes,
             tax: tax,
-            service_charge: service,
-            gratuity: grat,
+            included_tax: included_tax,
+            rounding: rounding,
+            total: total,
+            exempted: Vec::new(),
+            payments: self.payments.clone(),
+            paid: paid,
+            balance: (total - paid).max(Money::ZERO),
+            overpayment: overpayment,
+            // Anything paid over on other tenders stays on them as a credit
+            change_due: overpayment.min(cash),
         }
     }
-    
-    /*     // Generate all possibilities
-    fn calculate_all_possibilities(&self) -> Vec<f64> {
-        let item_count = self.items.len();
+}
+
+impl Receipt {
+    // Service charges and gratuities by order, service charges first on a tie
+    fn charges_in_order(&self) -> Vec<Charge<'_>> {
+        let mut charges: Vec<Charge> = self.service_charges.iter().map(|service_charge| Charge {
+            kind: ChargeKind::ServiceCharge,
+            id: service_charge.id,
+            name: &service_charge.name,
+            value: service_charge.value,
+            base: service_charge.base,
+            order: service_charge.order,
+            tax_group: &service_charge.tax_group,
+            exempt_taxes: &service_charge.exempt_taxes,
+            rules: &[],
+        }).chain(self.gratuities.iter().map(|gratuity| Charge {
+            kind: ChargeKind::Gratuity,
+            id: gratuity.id,
+            name: &gratuity.name,
+            value: ChargeValue::Percent(gratuity.rate),
+            base: gratuity.base,
+            order: gratuity.order,
+            tax_group: &gratuity.tax_group,
+            exempt_taxes: &gratuity.exempt_taxes,
+            rules: &gratuity.rules,
+        })).collect();
+
+        charges.sort_by_key(|charge| charge.order);
+        charges
+    }
+}
+
+// A service charge or gratuity, seen the same way by calculate_total
+struct Charge<'a> {
+    kind: ChargeKind,
+    id: i64,
+    name: &'a str,
+    value: ChargeValue,
+    base: ChargeBase,
+    order: i32,
+    tax_group: &'a TaxGroup,
+    exempt_taxes: &'a ExemptTaxes,
+    rules: &'a [GratuityRule],
+}
+
+// Running per-tax totals for a receipt. Taxes rounded per invoice also keep
+// their unrounded sum so the difference can be booked as a rounding adjustment.
+#[derive(Default)]
+struct TaxLedger {
+    taxes: Vec<TaxAmount>,
+    invoice_taxes: Vec<InvoiceTax>,
+}
 
-        // All combinations for item taxation: 2^item_count possibilities
-        let item_tax_combinations = (0..2_usize.pow(item_count as u32))
-            .map(|x| (0..item_count).map(|i| (x >> i) & 1 == 1).collect::<Vec<bool>>());
+struct InvoiceTax {
+    tax: Tax,
+    mode: RoundingMode,
+    exact: i128,
+    rounded: Money,
+}
+
+impl TaxLedger {
+    fn add(&mut self, new_taxes: &[TaxAmount], rounding: TaxRounding) {
+        for new_tax in new_taxes {
+            if let Some(existing) = self.taxes.iter_mut().find(|t| t.tax.id == new_tax.tax.id) {
+                existing.taxable += new_tax.taxable;
+                existing.amount += new_tax.amount;
+            } else {
+                self.taxes.push(new_tax.clone());
+            }
 
-        let mut results = Vec::new();
+            if rounding.level == RoundingLevel::PerInvoice {
+                let exact = new_tax.taxable.apply_rate_exact(new_tax.tax.rate);
 
-        for items_taxed in item_tax_combinations {
-            for sc_taxed in [true, false] {
-                for gr_taxed in [true, false] {
-                    let total = self.calculate_total(items_taxed.clone(), sc_taxed, gr_taxed);
-                    results.push(total);
+                if let Some(existing) = self.invoice_taxes.iter_mut().find(|t| t.tax.id == new_tax.tax.id && t.mode == rounding.mode) {
+                    existing.exact += exact;
+                    existing.rounded += new_tax.amount;
+                } else {
+                    self.invoice_taxes.push(InvoiceTax {
+                        tax: new_tax.tax.clone(),
+                        mode: rounding.mode,
+                        exact: exact,
+                        rounded: new_tax.amount,
+                    });
                 }
             }
         }
-        results
-    } */
-}
\ No newline at end of file
+    }
+
+    fn total(&self) -> Money {
+        self.taxes.iter().map(|t| t.amount).sum()
+    }
+
+    // Round the per-invoice sums and fold the differences into the tax totals
+    fn finish(mut self) -> (Vec<TaxAmount>, Vec<RoundingAdjustment>) {
+        let mut adjustments = Vec::new();
+
+        for invoice_tax in &self.invoice_taxes {
+            let rounded = Money::round_exact(invoice_tax.exact, invoice_tax.mode).expect("money overflow");
+            let adjustment = rounded - invoice_tax.rounded;
+
+            if !adjustment.is_zero() {
+                if let Some(existing) = self.taxes.iter_mut().find(|t| t.tax.id == invoice_tax.tax.id) {
+                    existing.amount += adjustment;
+                }
+                adjustments.push(RoundingAdjustment {
+                    reason: RoundingReason::InvoiceTax(invoice_tax.tax.clone()),
+                    amount: adjustment,
+                });
+            }
+        }
+
+        (self.taxes, adjustments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rounding::CashRounding;
+
+    fn tax_group(rates: &[&str]) -> TaxGroup {
+        let taxes = rates.iter().enumerate()
+            .map(|(index, rate)| Tax::new(index as i64 + 1, format!("Tax {}", index + 1), rate.parse().unwrap()))
+            .collect();
+        TaxGroup::new(1, "Test".to_string(), taxes)
+    }
+
+    #[test]
+    fn default_receipt_is_for_one_guest() {
+        let mut receipt = Receipt::default();
+        receipt.add_service_charge(ServiceCharge::new(1, "Setup".to_string(), ChargeValue::PerGuest(Money::from_cents(250)), ChargeBase::default(), 0, tax_group(&[]), ExemptTaxes::None));
+
+        assert_eq!(receipt.guests(), 1);
+        assert_eq!(receipt.calculate_total().service_charge(), Money::from_cents(250));
+    }
+
+    #[test]
+    fn minimum_spend_counts_items_in_its_categories() {
+        let mut receipt = Receipt::default();
+        receipt.add_item(Item::new(1, "Wings".to_string(), "Food".to_string(), Money::from_cents(1000), tax_group(&[])), Quantity::each(1));
+        receipt.add_item(Item::new(2, "Parking".to_string(), "Other".to_string(), Money::from_cents(500), tax_group(&[])), Quantity::each(1));
+        receipt.set_minimum_spend(MinimumSpend::new("F&B Minimum".to_string(), Money::from_cents(5000), vec!["food".to_string()], tax_group(&["10"])));
+
+        let breakdown = receipt.calculate_total();
+        assert_eq!(breakdown.shortfall(), Money::from_cents(4000));
+        assert_eq!(breakdown.tax, Money::from_cents(400));
+        assert_eq!(breakdown.total, Money::from_cents(1000 + 500 + 4000 + 400));
+    }
+
+    #[test]
+    fn modifiers_are_priced_taxed_and_charged_with_their_line() {
+        let mut receipt = Receipt::default();
+        receipt.add_item(Item::new(1, "Burger".to_string(), "Food".to_string(), Money::from_cents(1000), tax_group(&["10"])), Quantity::each(2));
+        receipt.add_modifier(0, Modifier::new(1, "Add Bacon".to_string(), Money::from_cents(200), tax_group(&["10"]), ExemptTaxes::None));
+        receipt.add_modifier(0, Modifier::new(2, "Side Salad".to_string(), Money::from_cents(300), tax_group(&["10"]), ExemptTaxes::All));
+        receipt.add_service_charge(ServiceCharge::new(1, "Service".to_string(), ChargeValue::Percent("10".parse().unwrap()), ChargeBase::Subtotal, 0, tax_group(&[]), ExemptTaxes::None));
+
+        let breakdown = receipt.calculate_total();
+        let line = &breakdown.lines[0];
+        // Each modifier is charged once for every burger
+        assert_eq!(line.modifiers[0].extended_price, Money::from_cents(400));
+        assert_eq!(line.gross_price(), Money::from_cents(2000 + 400 + 600));
+        // The salad is exempt, so only the burgers and the bacon are taxed
+        assert_eq!(line.modifiers[1].tax(), Money::ZERO);
+        assert_eq!(line.tax(), Money::from_cents(200 + 40));
+        // A charge on the subtotal takes the modifiers in
+        assert_eq!(breakdown.service_charge(), Money::from_cents(300));
+        assert_eq!(breakdown.total, Money::from_cents(3000 + 240 + 300));
+    }
+
+    #[test]
+    fn replacing_tax_groups_covers_the_minimum_spend() {
+        let mut receipt = Receipt::default();
+        receipt.set_minimum_spend(MinimumSpend::new("F&B Minimum".to_string(), Money::from_cents(5000), Vec::new(), tax_group(&["10"])));
+        receipt.replace_taxes(&tax_group(&["5"]).taxes);
+
+        assert_eq!(receipt.calculate_total().tax, Money::from_cents(250));
+    }
+
+    #[test]
+    fn inclusive_prices_keep_their_total() {
+        let mut inclusive = tax_group(&["10"]);
+        inclusive.tax_inclusive = true;
+        let mut receipt = Receipt::default();
+        receipt.add_item(Item::new(1, "Pint".to_string(), "Bar".to_string(), Money::from_cents(1000), inclusive.clone()), Quantity::each(3));
+        receipt.add_item(Item::new(2, "Wings".to_string(), "Food".to_string(), Money::from_cents(1000), tax_group(&["10"])), Quantity::each(1));
+
+        let breakdown = receipt.calculate_total();
+        assert_eq!(breakdown.lines[0].total(), Money::from_cents(3000));
+        assert_eq!(breakdown.included_tax, Money::from_cents(273));
+        assert_eq!(breakdown.tax, Money::from_cents(273 + 100));
+        assert_eq!(breakdown.total, Money::from_cents(3000 + 1000 + 100));
+    }
+
+    fn quarters(count: usize, tax_group: &TaxGroup, rounding: TaxRounding) -> ReceiptBreakdown {
+        let mut receipt = Receipt::default();
+        for id in 0..count {
+            receipt.add_item(Item::new(id as i64, "Mint".to_string(), "Food".to_string(), Money::from_cents(25), tax_group.clone()), Quantity::each(1));
+        }
+        receipt.set_rounding_policy(RoundingPolicy::new(rounding, CashRounding::None));
+        receipt.calculate_total()
+    }
+
+    #[test]
+    fn books_per_invoice_rounding_as_an_adjustment() {
+        // Five lines of 2.5 cents tax, 15 cents rounded line by line and 12.5 exactly
+        let half_up = quarters(5, &tax_group(&["10"]), TaxRounding::new(RoundingMode::HalfUp, RoundingLevel::PerInvoice));
+        assert_eq!(half_up.tax, Money::from_cents(13));
+        assert_eq!(half_up.rounding, vec![RoundingAdjustment { reason: RoundingReason::InvoiceTax(tax_group(&["10"]).taxes[0].clone()), amount: Money::from_cents(-2) }]);
+        assert_eq!(half_up.total, Money::from_cents(125 + 13));
+
+        let half_even = quarters(5, &tax_group(&["10"]), TaxRounding::new(RoundingMode::HalfEven, RoundingLevel::PerInvoice));
+        assert_eq!(half_even.tax, Money::from_cents(12));
+
+        let per_line = quarters(5, &tax_group(&["10"]), TaxRounding::new(RoundingMode::HalfUp, RoundingLevel::PerLine));
+        assert_eq!(per_line.tax, Money::from_cents(15));
+        assert!(per_line.rounding.is_empty());
+    }
+
+    #[test]
+    fn group_rounding_keeps_its_lines_out_of_the_invoice_rounding() {
+        let mut per_line = tax_group(&["10"]);
+        per_line.rounding = Some(TaxRounding::new(RoundingMode::HalfUp, RoundingLevel::PerLine));
+
+        let breakdown = quarters(5, &per_line, TaxRounding::new(RoundingMode::HalfUp, RoundingLevel::PerInvoice));
+        assert_eq!(breakdown.tax, Money::from_cents(15));
+        assert!(breakdown.rounding.is_empty());
+    }
+}
+
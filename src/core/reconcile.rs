@@ -0,0 +1,337 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    breakdown::ReceiptBreakdown,
+    money::{round_div, Money, Rate, RoundingMode, BASIS_POINT, RATE_SCALE},
+    receipt::Receipt,
+    rounding::{CashRounding, RoundingLevel, TaxRounding},
+};
+
+// A tax line as the venue printed it
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PrintedTax {
+    pub name: String,
+    pub amount: Money,
+}
+
+impl PrintedTax {
+    pub fn new(name: String, amount: Money) -> Self {
+        PrintedTax {
+            name: name,
+            amount: amount,
+        }
+    }
+}
+
+/// The figures on the venue's printed receipt. Anything left out isn't compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PrintedReceipt {
+    pub subtotal: Option<Money>,
+    // Matched to the calculated taxes by name
+    pub taxes: Vec<PrintedTax>,
+    pub service_charge: Option<Money>,
+    pub gratuity: Option<Money>,
+    pub total: Option<Money>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub enum Component {
+    Subtotal,
+    Tax(String),
+    ServiceCharge,
+    Gratuity,
+    Total,
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Component::Subtotal => write!(f, "Subtotal"),
+            Component::Tax(name) => write!(f, "{}", name),
+            Component::ServiceCharge => write!(f, "Service Charge"),
+            Component::Gratuity => write!(f, "Gratuity"),
+            Component::Total => write!(f, "Total"),
+        }
+    }
+}
+
+// Why a printed figure most likely differs from the calculated one
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub enum LikelyCause {
+    // The printed tax comes out exactly when rounded this way instead
+    Rounding(TaxRounding),
+    // The venue didn't tax this charge
+    UntaxedCharge(String),
+    // The venue taxed this charge and we don't
+    TaxedCharge(String),
+    // The printed tax is what this rate gives on the same taxable amount
+    WrongRate { expected: Rate, printed: Rate },
+    // The venue rounded the total to the nearest 5 cents
+    CashRounding,
+    // The venue didn't print this tax on a line of its own
+    NotPrinted,
+    // The difference is all from the components above it
+    Components,
+}
+
+impl fmt::Display for LikelyCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LikelyCause::Rounding(rounding) => {
+                let mode = match rounding.mode {
+                    RoundingMode::HalfUp => "half up",
+                    RoundingMode::HalfEven => "half even",
+                };
+                let level = match rounding.level {
+                    RoundingLevel::PerLine => "per line",
+                    RoundingLevel::PerInvoice => "per invoice",
+                };
+                write!(f, "tax rounded {}, {}", mode, level)
+            }
+            LikelyCause::UntaxedCharge(name) => write!(f, "{} wasn't taxed", name),
+            LikelyCause::TaxedCharge(name) => write!(f, "{} was taxed", name),
+            LikelyCause::WrongRate { expected, printed } => write!(f, "charged at {} instead of {}", printed, expected),
+            LikelyCause::CashRounding => write!(f, "total rounded to the nearest 5 cents"),
+            LikelyCause::NotPrinted => write!(f, "not on the printed receipt"),
+            LikelyCause::Components => write!(f, "carried from the lines above"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct ComponentDiff {
+    pub component: Component,
+    pub calculated: Money,
+    pub printed: Money,
+    // Printed less calculated
+    pub difference: Money,
+    // Only worked out when the figures differ
+    pub cause: Option<LikelyCause>,
+}
+
+impl ComponentDiff {
+    pub fn matches(&self) -> bool {
+        self.difference.is_zero()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Reconciliation {
+    pub components: Vec<ComponentDiff>,
+}
+
+impl Reconciliation {
+    pub fn is_reconciled(&self) -> bool {
+        self.components.iter().all(|component| component.matches())
+    }
+
+    pub fn differences(&self) -> Vec<&ComponentDiff> {
+        self.components.iter().filter(|component| !component.matches()).collect()
+    }
+}
+
+/// Compares a receipt's calculated figures with the printed ones, line by line.
+pub fn reconcile(receipt: &Receipt, printed: &PrintedReceipt) -> Reconciliation {
+    let breakdown = receipt.calculate_total();
+    let mut components = Vec::new();
+
+    if let Some(subtotal) = printed.subtotal {
+        components.push(compare(Component::Subtotal, breakdown.subtotal, subtotal, None));
+    }
+
+    // Every calculated tax is compared once any tax line is entered. Taxes the venue
+    // didn't print are reported as such, unless they add up to the one printed
+    // line we have no tax for, like a single combined "Tax".
+    if !printed.taxes.is_empty() {
+        let is_printed = |name: &str| printed.taxes.iter().any(|t| t.name.eq_ignore_ascii_case(name));
+        let is_calculated = |name: &str| breakdown.taxes.iter().any(|t| t.tax.name.eq_ignore_ascii_case(name));
+        let unprinted: Vec<_> = breakdown.taxes.iter().filter(|t| !is_printed(&t.tax.name)).collect();
+        let extra: Vec<&PrintedTax> = printed.taxes.iter().filter(|t| !is_calculated(&t.name)).collect();
+        let combined = match extra.as_slice() {
+            [line] if !unprinted.is_empty() => Some(*line),
+            _ => None,
+        };
+
+        for calculated in &breakdown.taxes {
+            let name = &calculated.tax.name;
+            match printed.taxes.iter().find(|t| t.name.eq_ignore_ascii_case(name)) {
+                Some(tax) => {
+                    let cause = tax_cause(receipt, &breakdown, name, tax.amount);
+                    components.push(compare(Component::Tax(name.clone()), calculated.amount, tax.amount, cause));
+                }
+                None if combined.is_none() => {
+                    components.push(compare(Component::Tax(name.clone()), calculated.amount, Money::ZERO, Some(LikelyCause::NotPrinted)));
+                }
+                None => {}
+            }
+        }
+        for tax in extra {
+            let calculated = match combined {
+                Some(_) => unprinted.iter().map(|t| t.amount).sum(),
+                None => Money::ZERO,
+            };
+            components.push(compare(Component::Tax(tax.name.clone()), calculated, tax.amount, None));
+        }
+    }
+
+    if let Some(service_charge) = printed.service_charge {
+        components.push(compare(Component::ServiceCharge, breakdown.service_charge(), service_charge, None));
+    }
+    if let Some(gratuity) = printed.gratuity {
+        components.push(compare(Component::Gratuity, breakdown.gratuity(), gratuity, None));
+    }
+
+    if let Some(total) = printed.total {
+        // A tax left off the printed lines can still be in the printed total
+        let carried: Money = components.iter()
+            .filter(|component| component.cause != Some(LikelyCause::NotPrinted))
+            .map(|component| component.difference)
+            .sum();
+        let cause = if !carried.is_zero() && carried == total - breakdown.total {
+            Some(LikelyCause::Components)
+        } else if CashRounding::Nickel.round(breakdown.total + carried) == total {
+            Some(LikelyCause::CashRounding)
+        } else {
+            None
+        };
+        components.push(compare(Component::Total, breakdown.total, total, cause));
+    }
+
+    Reconciliation {
+        components: components,
+    }
+}
+
+fn compare(component: Component, calculated: Money, printed: Money, cause: Option<LikelyCause>) -> ComponentDiff {
+    let difference = printed - calculated;
+
+    ComponentDiff {
+        component: component,
+        calculated: calculated,
+        printed: printed,
+        difference: difference,
+        cause: if difference.is_zero() { None } else { cause },
+    }
+}
+
+// Tries each explanation in turn, from the smallest change to the receipt up
+fn tax_cause(receipt: &Receipt, breakdown: &ReceiptBreakdown, name: &str, printed: Money) -> Option<LikelyCause> {
+    let calculated = breakdown.taxes.iter().find(|t| t.tax.name.eq_ignore_ascii_case(name))?;
+    let difference = printed - calculated.amount;
+    if difference.is_zero() {
+        return None;
+    }
+
+    // Another rounding mode or level, on the receipt or set on a tax group
+    let policy = receipt.rounding_policy();
+    for mode in RoundingMode::ALL {
+        for level in RoundingLevel::ALL {
+            let rounding = TaxRounding::new(mode, level);
+
+            let mut rerounded = receipt.clone();
+            rerounded.set_group_rounding(Some(rounding));
+            let amount = rerounded.calculate_total().taxes.into_iter()
+                .find(|t| t.tax.id == calculated.tax.id)
+                .map(|t| t.amount);
+            if amount == Some(printed) {
+                return Some(LikelyCause::Rounding(rounding));
+            }
+        }
+    }
+
+    // A charge taxed on one side and not the other
+    for charge in &breakdown.charges {
+        match charge.taxes.iter().find(|t| t.tax.id == calculated.tax.id) {
+            Some(charge_tax) if charge_tax.amount == -difference => {
+                return Some(LikelyCause::UntaxedCharge(charge.name.clone()));
+            }
+            None if charge.amount.apply_rate(calculated.tax.rate, policy.tax.mode) == difference => {
+                return Some(LikelyCause::TaxedCharge(charge.name.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    // A different rate on the same taxable amount, to the basis point
+    if !calculated.taxable.is_zero() {
+        let basis_points = round_div(
+            printed.cents() as i128 * RATE_SCALE as i128,
+            calculated.taxable.cents() as i128 * BASIS_POINT as i128,
+            RoundingMode::HalfUp,
+        );
+        let rate = Rate::from_basis_points(basis_points as i64);
+        if rate != calculated.tax.rate && calculated.taxable.apply_rate(rate, policy.tax.mode) == printed {
+            return Some(LikelyCause::WrongRate { expected: calculated.tax.rate, printed: rate });
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::items::Item;
+    use crate::core::quantity::Quantity;
+    use crate::core::rounding::RoundingPolicy;
+    use crate::core::tax::Tax;
+    use crate::core::tax_group::TaxGroup;
+
+    fn receipt(cents: &[i64], tax_group: &TaxGroup) -> Receipt {
+        let mut receipt = Receipt::default();
+        for (id, cents) in cents.iter().enumerate() {
+            let item = Item::new(id as i64, format!("Item {}", id), "Food".to_string(), Money::from_cents(*cents), tax_group.clone());
+            receipt.add_item(item, Quantity::each(1));
+        }
+        receipt
+    }
+
+    fn printed_taxes(taxes: &[(&str, i64)]) -> PrintedReceipt {
+        PrintedReceipt {
+            taxes: taxes.iter().map(|(name, cents)| PrintedTax::new(name.to_string(), Money::from_cents(*cents))).collect(),
+            ..PrintedReceipt::default()
+        }
+    }
+
+    #[test]
+    fn finds_rounding_set_on_the_tax_group() {
+        let mut sales = TaxGroup::new(1, "Sales".to_string(), vec![Tax::new(1, "Sales".to_string(), "10".parse().unwrap())]);
+        sales.rounding = Some(TaxRounding::new(RoundingMode::HalfUp, RoundingLevel::PerLine));
+        let mut receipt = receipt(&[25, 25], &sales);
+        receipt.set_rounding_policy(RoundingPolicy::new(TaxRounding::new(RoundingMode::HalfUp, RoundingLevel::PerInvoice), CashRounding::None));
+
+        // 2.5 cents a line rounds up twice, the venue rounded the 5 cents once
+        let reconciliation = reconcile(&receipt, &printed_taxes(&[("Sales", 5)]));
+        assert_eq!(reconciliation.components[0].calculated, Money::from_cents(6));
+        assert_eq!(reconciliation.components[0].cause, Some(LikelyCause::Rounding(TaxRounding::new(RoundingMode::HalfUp, RoundingLevel::PerInvoice))));
+    }
+
+    #[test]
+    fn reports_taxes_left_off_the_printed_receipt() {
+        let sales = TaxGroup::new(1, "Sales".to_string(), vec![
+            Tax::new(1, "State".to_string(), "4".parse().unwrap()),
+            Tax::new(2, "City".to_string(), "2".parse().unwrap()),
+        ]);
+        let receipt = receipt(&[10_000], &sales);
+
+        let reconciliation = reconcile(&receipt, &printed_taxes(&[("State", 400)]));
+        let city = &reconciliation.components[1];
+        assert_eq!(city.component, Component::Tax("City".to_string()));
+        assert_eq!(city.cause, Some(LikelyCause::NotPrinted));
+    }
+
+    #[test]
+    fn compares_a_combined_tax_line_with_the_taxes_it_covers() {
+        let sales = TaxGroup::new(1, "Sales".to_string(), vec![
+            Tax::new(1, "State".to_string(), "4".parse().unwrap()),
+            Tax::new(2, "City".to_string(), "2".parse().unwrap()),
+        ]);
+        let receipt = receipt(&[10_000], &sales);
+
+        let reconciliation = reconcile(&receipt, &printed_taxes(&[("Tax", 600)]));
+        assert_eq!(reconciliation.components.len(), 1);
+        assert_eq!(reconciliation.components[0].component, Component::Tax("Tax".to_string()));
+        assert!(reconciliation.is_reconciled());
+    }
+}
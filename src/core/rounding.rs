@@ -0,0 +1,136 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use crate::core::money::{round_div, Money, RoundingMode};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RoundingLevel {
+    // Every line's tax is rounded to the cent on its own
+    #[default]
+    PerLine,
+    // Each tax is added up unrounded across the receipt and rounded once
+    PerInvoice,
+}
+
+impl RoundingLevel {
+    pub const ALL: [RoundingLevel; 2] = [RoundingLevel::PerLine, RoundingLevel::PerInvoice];
+}
+
+impl fmt::Display for RoundingLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundingLevel::PerLine => write!(f, "Per line"),
+            RoundingLevel::PerInvoice => write!(f, "Per invoice"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CashRounding {
+    #[default]
+    None,
+    // Round the amount due to the nearest 5 cents
+    Nickel,
+}
+
+impl CashRounding {
+    pub const ALL: [CashRounding; 2] = [CashRounding::None, CashRounding::Nickel];
+
+    pub fn round(&self, amount: Money) -> Money {
+        match self {
+            CashRounding::None => amount,
+            CashRounding::Nickel => {
+                Money::from_cents(round_div(amount.cents() as i128, 5, RoundingMode::HalfUp) as i64 * 5)
+            }
+        }
+    }
+}
+
+impl fmt::Display for CashRounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CashRounding::None => write!(f, "To the cent"),
+            CashRounding::Nickel => write!(f, "To 5 cents"),
+        }
+    }
+}
+
+// How tax amounts are rounded, a tax group can carry its own
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TaxRounding {
+    pub mode: RoundingMode,
+    pub level: RoundingLevel,
+}
+
+impl TaxRounding {
+    pub fn new(mode: RoundingMode, level: RoundingLevel) -> Self {
+        TaxRounding {
+            mode: mode,
+            level: level,
+        }
+    }
+}
+
+impl fmt::Display for TaxRounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.mode, self.level.to_string().to_lowercase())
+    }
+}
+
+/// A tax group's rounding as picked in a form, the receipt's unless the group
+/// carries its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupRounding {
+    Receipt,
+    Own(TaxRounding),
+}
+
+impl GroupRounding {
+    pub fn options() -> Vec<GroupRounding> {
+        let mut options = vec![GroupRounding::Receipt];
+        for mode in RoundingMode::ALL {
+            for level in RoundingLevel::ALL {
+                options.push(GroupRounding::Own(TaxRounding::new(mode, level)));
+            }
+        }
+        options
+    }
+
+    pub fn rounding(&self) -> Option<TaxRounding> {
+        match self {
+            GroupRounding::Receipt => None,
+            GroupRounding::Own(rounding) => Some(*rounding),
+        }
+    }
+}
+
+impl From<Option<TaxRounding>> for GroupRounding {
+    fn from(rounding: Option<TaxRounding>) -> Self {
+        rounding.map_or(GroupRounding::Receipt, GroupRounding::Own)
+    }
+}
+
+impl fmt::Display for GroupRounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupRounding::Receipt => write!(f, "Receipt's rounding"),
+            GroupRounding::Own(rounding) => write!(f, "{}", rounding),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RoundingPolicy {
+    // Used for every tax group without its own rounding, and for charges
+    pub tax: TaxRounding,
+    pub cash: CashRounding,
+}
+
+impl RoundingPolicy {
+    pub fn new(tax: TaxRounding, cash: CashRounding) -> Self {
+        RoundingPolicy {
+            tax: tax,
+            cash: cash,
+        }
+    }
+}
@@ -1,32 +1,42 @@
 use iced::widget::{button, column, row, text, text_input, Container};
 use iced::{Color, Element, Length};
-use std::hash::{Hash, Hasher};
 
+use crate::core::charge::{ChargeBase, ChargeValue};
+use crate::core::currency::MoneyFormat;
+use crate::core::exemption::ExemptTaxes;
+use crate::core::money::Rate;
 use crate::core::tax_group::TaxGroup;
 use crate::ui::custom_appearances::{pos_table_header, pos_table_row};
 use crate::ui::service_charge_list_view::{Message, ServiceChargeEditState};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ServiceCharge {
     pub id: i64,
     pub name: String,
-    pub percent: f64,
+    // A percent of the base, a flat fee or a fee per guest
+    pub value: ChargeValue,
+    // Only used by percent charges
+    pub base: ChargeBase,
+    // Charges on a receipt are applied from the lowest order up
+    pub order: i32,
     pub tax_group: TaxGroup,
-    pub tax_overide: bool,
+    // Taxes in the group it doesn't pay
+    pub exempt_taxes: ExemptTaxes,
     
 }
 
 impl ServiceCharge {
-    pub fn new(id: i64, name: String, percent: f64, tax_group: TaxGroup, tax_overide: bool) -> Self {
-        let tax_as_percentage = percent * 100.0;
+    pub fn new(id: i64, name: String, value: ChargeValue, base: ChargeBase, order: i32, tax_group: TaxGroup, exempt_taxes: ExemptTaxes) -> Self {
 
         
         ServiceCharge {
             id: id,
             name: name,
-            percent: percent,
+            value: value,
+            base: base,
+            order: order,
             tax_group: tax_group,
-            tax_overide: tax_overide,
+            exempt_taxes: exempt_taxes,
         }
     }
 }
@@ -36,36 +46,26 @@ impl Default for ServiceCharge {
         Self {
             id: 1,
             name: "Banquet".to_string(),
-            percent: 3.5,
+            value: ChargeValue::Percent(Rate::from_basis_points(350)),
+            base: ChargeBase::default(),
+            order: 0,
             tax_group: TaxGroup::default(),
-            tax_overide: false,
+            exempt_taxes: ExemptTaxes::None,
         }
     }
 }
 
-impl Eq for ServiceCharge {}
 
-impl Hash for ServiceCharge {
-    fn hash<H: Hasher>(&self, state: &mut H){
-        self.id.hash(state);
-        self.name.hash(state);
-        self.tax_group.hash(state);
-        self.tax_overide.hash(state);
-
-        let percent_bits = self.percent.to_bits();
-        percent_bits.hash(state);
-    }
-}
-
-
-pub fn create_service_charge_table(service_charges: Vec<ServiceCharge>, edit_states: &mut std::collections::HashMap<i64, ServiceChargeEditState>) -> Element<'static, Message> {
+pub fn create_service_charge_table(service_charges: Vec<ServiceCharge>, format: MoneyFormat, edit_states: &mut std::collections::HashMap<i64, ServiceChargeEditState>) -> Element<'static, Message> {
     
     let header = row![
         table_cell("ID".to_string(), true, 75_f32, 0, false, "".to_string(), edit_states),
         table_cell("Name".to_string(), true, 150_f32, 0, false, "".to_string(), edit_states),
-        table_cell("Percent".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Amount".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Base".to_string(), true, 150_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Order".to_string(), true, 75_f32, 0, false, "".to_string(), edit_states),
         table_cell("Tax Group".to_string(),true, 150_f32, 0, false, "".to_string(), edit_states),
-        table_cell("Taxed".to_string(),true, 75_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Exempt From".to_string(),true, 100_f32, 0, false, "".to_string(), edit_states),
         table_cell("Actions".to_string(), true, 200_f32, 0, false, "".to_string(), edit_states),
     ]
     .spacing(1)
@@ -84,9 +84,11 @@ pub fn create_service_charge_table(service_charges: Vec<ServiceCharge>, edit_sta
                     row![
                     table_cell(service_charge.id.to_string(), false, 75_f32, service_charge.id, false, "id".to_string(), edit_states),
                     table_cell(service_charge.name.clone(), false, 150_f32, service_charge.id, is_editing, "name".to_string(), edit_states),
-                    table_cell(format!("${:.2}", service_charge.percent), false, 100_f32, service_charge.id, is_editing, "percent".to_string(), edit_states),
+                    table_cell(service_charge.value.describe(&format), false, 100_f32, service_charge.id, is_editing, "value".to_string(), edit_states),
+                    table_cell(service_charge.base.to_string(), false, 150_f32, 0, false, "base".to_string(), edit_states),
+                    table_cell(service_charge.order.to_string(), false, 75_f32, service_charge.id, is_editing, "order".to_string(), edit_states),
                     table_cell(service_charge.tax_group.name.clone(),false, 150_f32, 0, false, "tax_group".to_string(), edit_states),
-                    table_cell(service_charge.tax_overide.to_string(),false, 75_f32, 0, false, "tax_overide".to_string(), edit_states),
+                    table_cell(service_charge.exempt_taxes.describe(&service_charge.tax_group),false, 100_f32, service_charge.id, is_editing, "exempt_taxes".to_string(), edit_states),
                     table_cell_with_action(
                         service_charge.id, 
                         210_f32,
@@ -139,7 +141,9 @@ pub fn table_cell<'a>(
             let current_value = if let Some(edit_state) = edit_states.get(&service_charge_id) {
                 match field_name.as_str() {
                     "name" => edit_state.name.clone(),
-                    "percent" => edit_state.percent.clone(),
+                    "value" => edit_state.value.clone(),
+                    "order" => edit_state.order.clone(),
+                    "exempt_taxes" => edit_state.exempt_taxes.clone(),
                     _ => content.clone()
                 }
             } else {
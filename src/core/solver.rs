@@ -0,0 +1,602 @@
+pub mod catalog;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::core::{
+    charge::ChargeBase,
+    discount::{DiscountTiming, DiscountValue},
+    gratuity::GratuityCondition,
+    money::{Money, Rate},
+    receipt::Receipt,
+    rounding::{CashRounding, RoundingPolicy},
+    tax::Tax,
+};
+
+// Highest combined rate the rate search will consider, 100%
+const MAX_RATE: Rate = Rate::from_basis_points(10_000);
+
+// Most service charges and gratuities whose taxability is searched, every
+// combination of them is tried
+const MAX_CHARGES: usize = 16;
+
+// Steps tried when picking a suggested rate, coarsest first, in millionths
+const RATE_STEPS: [i64; 12] = [10_000, 5_000, 2_500, 1_000, 500, 250, 100, 50, 25, 10, 5, 1];
+
+// One way the receipt could have been taxed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Configuration {
+    pub taxed_lines: Vec<bool>,
+    pub taxed_service_charges: Vec<bool>,
+    pub taxed_gratuities: Vec<bool>,
+}
+
+impl Configuration {
+    pub fn apply(&self, receipt: &Receipt) -> Receipt {
+        let mut receipt = receipt.clone();
+
+        for (index, taxed) in self.taxed_lines.iter().enumerate() {
+            receipt.set_line_taxed(index, *taxed);
+        }
+        for (index, taxed) in self.taxed_service_charges.iter().enumerate() {
+            receipt.set_service_charge_taxed(index, *taxed);
+        }
+        for (index, taxed) in self.taxed_gratuities.iter().enumerate() {
+            receipt.set_gratuity_taxed(index, *taxed);
+        }
+
+        receipt
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub configuration: Configuration,
+    pub total: Money,
+    // Calculated total minus the target
+    pub difference: Money,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SolveError {
+    #[error("the receipt has {charges} service charges and gratuities, at most {max} can be searched")]
+    TooManyCharges { charges: usize, max: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolveLimits {
+    pub max_results: usize,
+}
+
+impl Default for SolveLimits {
+    fn default() -> Self {
+        Self {
+            max_results: 25,
+        }
+    }
+}
+
+/// Finds taxability configurations whose total lands within `tolerance` of `target`.
+///
+/// For each combination of taxed charges the receipt is priced once with no
+/// line taxed and once per line with only that line taxed, which gives what
+/// taxing each line adds to the total. A partial configuration is dropped as
+/// soon as the target falls outside the range those amounts allow for the
+/// remaining lines, widened by a cent for every rounding that could go the
+/// other way. Only the configurations that survive are priced in full.
+/// Every combination of taxed charges is tried, there are only ever a few.
+/// Solutions come back closest match first, at most `limits.max_results` of them.
+/// Once that many are found a branch is also dropped when it can't beat the
+/// worst of them, so the closest matches are kept whatever order they turn up in.
+pub fn solve(receipt: &Receipt, target: Money, tolerance: Money, limits: SolveLimits) -> Result<Vec<Solution>, SolveError> {
+    let line_count = receipt.order().len();
+    let service_charge_count = receipt.service_charges().len();
+    let charge_count = service_charge_count + receipt.gratuities().len();
+    if charge_count > MAX_CHARGES {
+        return Err(SolveError::TooManyCharges { charges: charge_count, max: MAX_CHARGES });
+    }
+    let mut best = Best {
+        solutions: BinaryHeap::new(),
+        max_results: limits.max_results,
+    };
+
+    // Cash rounding is left out of the line amounts and allowed for once
+    let mut unrounded = receipt.clone();
+    unrounded.set_rounding_policy(RoundingPolicy::new(receipt.rounding_policy().tax, CashRounding::None));
+    let cash_allowance = if receipt.rounding_policy().cash == CashRounding::None { 0 } else { 2 };
+    let allowance = rounding_allowance(receipt);
+
+    for taxed_charges in 0..1_u64 << charge_count {
+        let taxed = (0..charge_count).map(|index| taxed_charges & (1 << index) != 0).collect::<Vec<_>>();
+        let mut configuration = Configuration {
+            taxed_lines: vec![false; line_count],
+            taxed_service_charges: taxed[..service_charge_count].to_vec(),
+            taxed_gratuities: taxed[service_charge_count..].to_vec(),
+        };
+
+        let untaxed_receipt = configuration.apply(&unrounded);
+        let untaxed = untaxed_receipt.total();
+        let line_tax: Vec<i64> = (0..line_count).map(|index| {
+            configuration.taxed_lines[index] = true;
+            let taxed = configuration.apply(&unrounded).total();
+            configuration.taxed_lines[index] = false;
+            (taxed - untaxed).cents()
+        }).collect();
+
+        let search = Search {
+            receipt: receipt,
+            target: target.cents(),
+            tolerance: tolerance.cents(),
+            untaxed: untaxed.cents(),
+            lowest_after: suffix_sums(&line_tax, |tax| tax.min(0)),
+            highest_after: suffix_sums(&line_tax, |tax| tax.max(0)),
+            line_tax: line_tax,
+            allowance: allowance,
+            fixed_allowance: cash_allowance + switching_allowance(&untaxed_receipt),
+        };
+        search.run(&mut configuration, 0, 0, 0, &mut best);
+    }
+
+    Ok(best.solutions.into_sorted_vec().into_iter().map(|ranked| ranked.0).collect())
+}
+
+// Closer matches rank first, then those taxing fewer lines
+struct Ranked(Solution);
+
+impl Ranked {
+    fn rank(&self) -> (Money, usize) {
+        let taxed_count = self.0.configuration.taxed_lines.iter().filter(|taxed| **taxed).count();
+        (self.0.difference.abs(), taxed_count)
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank() == other.rank()
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+// The closest solutions found so far, the worst of them on top to be pushed out
+struct Best {
+    solutions: BinaryHeap<Ranked>,
+    max_results: usize,
+}
+
+impl Best {
+    // Furthest from the target a new solution can be and still be kept
+    fn tolerance(&self, tolerance: i64) -> i64 {
+        if self.max_results == 0 {
+            return -1;
+        }
+        match self.solutions.peek() {
+            Some(worst) if self.solutions.len() >= self.max_results => tolerance.min(worst.0.difference.abs().cents()),
+            _ => tolerance,
+        }
+    }
+
+    fn offer(&mut self, solution: Solution) {
+        let solution = Ranked(solution);
+        if self.solutions.len() < self.max_results {
+            self.solutions.push(solution);
+        } else if let Some(mut worst) = self.solutions.peek_mut() {
+            if solution < *worst {
+                *worst = solution;
+            }
+        }
+    }
+}
+
+// Cents a line's measured tax can be off by once other lines are taxed with it,
+// one for every amount rounded on the way to the total that the line moves
+fn rounding_allowance(receipt: &Receipt) -> i64 {
+    let tax_groups = receipt.order().iter()
+        .flat_map(|(item, _)| std::iter::once(&item.tax_group).chain(item.modifiers.iter().map(|m| &m.tax_group)))
+        .chain(receipt.service_charges().iter().map(|s| &s.tax_group))
+        .chain(receipt.gratuities().iter().map(|g| &g.tax_group))
+        .chain(receipt.minimum_spend().map(|m| &m.tax_group));
+    let tax_count = tax_groups.map(|tax_group| tax_group.taxes.len()).max().unwrap_or(0) as i64;
+    let charge_count = (receipt.service_charges().len() + receipt.gratuities().len()) as i64
+        + if receipt.minimum_spend().is_some() { 1 } else { 0 };
+
+    tax_count * (1 + charge_count) + charge_count + receipt.discounts().len() as i64
+}
+
+// Cents the total can jump by as lines are taxed, from amounts that are capped or
+// switch on at a threshold instead of following the tax. `receipt` has no line taxed.
+fn switching_allowance(receipt: &Receipt) -> i64 {
+    // Post-tax discounts for an amount stop growing with the tax once they are used up
+    let fixed_discounts: i64 = receipt.discounts().iter()
+        .filter(|discount| discount.timing == DiscountTiming::PostTax)
+        .map(|discount| match discount.value {
+            DiscountValue::Fixed(amount) => amount.cents(),
+            DiscountValue::Percent(_) => 0,
+        })
+        .sum();
+
+    // Backing tax out of an inclusive price lowers the subtotal, which moves the
+    // minimum spend shortfall and can cross an automatic gratuity's threshold
+    let inclusive = receipt.order().iter()
+        .any(|(item, _)| item.is_tax_inclusive() || item.modifiers.iter().any(|m| m.tax_group.tax_inclusive));
+    if !inclusive {
+        return fixed_discounts;
+    }
+    let minimum = receipt.minimum_spend().map_or(0, |minimum_spend| minimum_spend.minimum.cents());
+
+    fixed_discounts + minimum + gratuity_switching(receipt)
+}
+
+// How far gratuities that switch on at a subtotal can move the total. A gratuity
+// is charged on the subtotal, which is highest with every inclusive price left
+// whole, so it never moves the total further than charging it on `untaxed` does.
+fn gratuity_switching(untaxed: &Receipt) -> i64 {
+    let mut charged = untaxed.clone();
+    let mut dropped = untaxed.clone();
+    let mut switches = false;
+    for (index, gratuity) in untaxed.gratuities().iter().enumerate() {
+        if !gratuity.rules.iter().any(|rule| matches!(rule.condition, GratuityCondition::Subtotal(_))) {
+            continue;
+        }
+        switches = true;
+
+        // Before discounts is the larger of the two subtotals a rule charges on
+        let mut always = gratuity.clone();
+        always.rules.clear();
+        always.base = ChargeBase::Subtotal;
+        charged.set_gratuity(index, always);
+
+        let mut never = gratuity.clone();
+        never.rate = Rate::ZERO;
+        dropped.set_gratuity(index, never);
+    }
+
+    if switches { (charged.total() - dropped.total()).abs().cents() } else { 0 }
+}
+
+// sums[i] adds up `part` of every amount from i on
+fn suffix_sums(amounts: &[i64], part: impl Fn(i64) -> i64) -> Vec<i64> {
+    let mut sums = vec![0; amounts.len() + 1];
+    for index in (0..amounts.len()).rev() {
+        sums[index] = sums[index + 1] + part(amounts[index]);
+    }
+    sums
+}
+
+// One combination of taxed charges, with what taxing each line adds to its total
+struct Search<'a> {
+    receipt: &'a Receipt,
+    target: i64,
+    tolerance: i64,
+    untaxed: i64,
+    line_tax: Vec<i64>,
+    lowest_after: Vec<i64>,
+    highest_after: Vec<i64>,
+    allowance: i64,
+    fixed_allowance: i64,
+}
+
+impl Search<'_> {
+    fn run(&self, configuration: &mut Configuration, index: usize, added: i64, taxed_count: i64, best: &mut Best) {
+        let line_count = self.line_tax.len();
+        let tolerance = best.tolerance(self.tolerance);
+
+        // Each taxed line can pull the total a little off what its own tax says
+        let slack = self.allowance * (taxed_count + (line_count - index) as i64) + self.fixed_allowance;
+        let lowest = self.untaxed + added + self.lowest_after[index] - slack;
+        let highest = self.untaxed + added + self.highest_after[index] + slack;
+        if lowest - tolerance > self.target || highest + tolerance < self.target {
+            return;
+        }
+
+        if index == line_count {
+            let total = configuration.apply(self.receipt).total();
+            let difference = total - Money::from_cents(self.target);
+            if difference.abs().cents() <= tolerance {
+                best.offer(Solution {
+                    configuration: configuration.clone(),
+                    total,
+                    difference,
+                });
+            }
+            return;
+        }
+
+        for taxed in [false, true] {
+            configuration.taxed_lines[index] = taxed;
+            let (added, taxed_count) = if taxed {
+                (added + self.line_tax[index], taxed_count + 1)
+            } else {
+                (added, taxed_count)
+            };
+
+            self.run(configuration, index + 1, added, taxed_count, best);
+        }
+        configuration.taxed_lines[index] = false;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaxMatch {
+    pub tax: Tax,
+    // How far the tax's rate is from the range that fits, zero if it fits
+    pub distance: Rate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateInference {
+    // Every combined rate from lowest to highest reproduces the target
+    pub lowest: Rate,
+    pub highest: Rate,
+    // The roundest rate in the range, to create through the add tax form
+    pub suggested: Rate,
+    // Known taxes, closest to the range first
+    pub nearest_taxes: Vec<TaxMatch>,
+}
+
+/// Works out the single combined tax rate that turns `receipt` into `target`.
+///
+/// Every line and charge that pays any of its taxes is charged the unknown rate
+/// instead of its tax group. One that pays none, through its own exemption or
+/// the customer's, stays untaxed. Returns `None` when no rate up to 100% lands
+/// within `tolerance`.
+///
+/// Only one rate is inferred. When the receipt was charged several taxes, such
+/// as a state and a city tax, the result is their sum; a single total can't say
+/// how that sum splits between them, so each is matched against known taxes or
+/// entered separately.
+pub fn infer_tax_rate(receipt: &Receipt, target: Money, tolerance: Money, known_taxes: &[Tax]) -> Option<RateInference> {
+    let total_at = |rate: Rate| {
+        let mut receipt = receipt.clone();
+        receipt.replace_taxes(&[Tax::new(0, "Unknown".to_string(), rate)]);
+        receipt.total()
+    };
+
+    // The total never falls as the rate rises, so both ends can be binary searched
+    let lowest = first_rate(MAX_RATE, |rate| total_at(rate) >= target - tolerance)?;
+    let highest = first_rate(MAX_RATE, |rate| total_at(rate) > target + tolerance)
+        .map(|rate| Rate::from_millionths(rate.millionths() - 1))
+        .unwrap_or(MAX_RATE);
+
+    if lowest > highest {
+        return None;
+    }
+
+    let mut nearest_taxes: Vec<TaxMatch> = known_taxes.iter().map(|tax| {
+        let distance = if tax.rate < lowest {
+            lowest.millionths() - tax.rate.millionths()
+        } else if tax.rate > highest {
+            tax.rate.millionths() - highest.millionths()
+        } else {
+            0
+        };
+
+        TaxMatch {
+            tax: tax.clone(),
+            distance: Rate::from_millionths(distance),
+        }
+    }).collect();
+    nearest_taxes.sort_by_key(|tax_match| tax_match.distance);
+
+    Some(RateInference {
+        lowest,
+        highest,
+        suggested: roundest_rate(lowest, highest),
+        nearest_taxes,
+    })
+}
+
+// Smallest rate up to `max` where `condition` holds, assuming it stays true once it is
+fn first_rate(max: Rate, condition: impl Fn(Rate) -> bool) -> Option<Rate> {
+    if !condition(max) {
+        return None;
+    }
+
+    let (mut low, mut high) = (0, max.millionths());
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if condition(Rate::from_millionths(middle)) {
+            high = middle;
+        } else {
+            low = middle + 1;
+        }
+    }
+
+    Some(Rate::from_millionths(low))
+}
+
+// The rate in the range with the fewest decimal places, nearest the middle
+fn roundest_rate(lowest: Rate, highest: Rate) -> Rate {
+    let middle = (lowest.millionths() + highest.millionths()) / 2;
+
+    for step in RATE_STEPS {
+        let below = middle - middle.rem_euclid(step);
+        let candidate = if middle - below <= step / 2 { below } else { below + step };
+
+        for rate in [candidate, below, below + step] {
+            if rate >= lowest.millionths() && rate <= highest.millionths() {
+                return Rate::from_millionths(rate);
+            }
+        }
+    }
+
+    Rate::from_millionths(middle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tax_group::TaxGroup;
+    use crate::core::exemption::{ExemptTaxes, TaxExemption};
+    use crate::core::gratuity::{Gratuity, GratuityRule, RuleBase};
+    use crate::core::items::Item;
+    use crate::core::modifier::Modifier;
+    use crate::core::quantity::Quantity;
+
+    fn state_and_city() -> TaxGroup {
+        TaxGroup::new(1, "Sales".to_string(), vec![
+            Tax::new(1, "State".to_string(), "4".parse().unwrap()),
+            Tax::new(2, "City".to_string(), "2".parse().unwrap()),
+        ])
+    }
+
+    fn item(id: i64, cents: i64, tax_group: &TaxGroup, exempt_taxes: ExemptTaxes) -> Item {
+        Item::new(id, format!("Item {}", id), "Food".to_string(), Money::from_cents(cents), tax_group.clone()).with_exempt_taxes(exempt_taxes)
+    }
+
+    #[test]
+    fn infers_the_combined_rate_of_several_taxes() {
+        let mut receipt = Receipt::default();
+        receipt.add_item(item(1, 10_000, &state_and_city(), ExemptTaxes::None), Quantity::each(1));
+        assert_eq!(receipt.calculate_total().total, Money::from_cents(10_600));
+
+        let known = state_and_city().taxes;
+        let inference = infer_tax_rate(&receipt, Money::from_cents(10_600), Money::ZERO, &known).unwrap();
+        assert_eq!(inference.suggested, "6".parse().unwrap());
+        assert!(inference.lowest <= inference.suggested && inference.suggested <= inference.highest);
+        // Neither tax alone is the combined rate
+        assert!(inference.nearest_taxes.iter().all(|tax_match| tax_match.distance > Rate::ZERO));
+    }
+
+    #[test]
+    fn infers_a_rate_without_taxing_exempt_lines() {
+        let liquor = TaxGroup::new(2, "Liquor".to_string(), vec![Tax::new(3, "Liquor".to_string(), "10".parse().unwrap())]);
+        let mut receipt = Receipt::default();
+        receipt.add_item(item(1, 10_000, &liquor, ExemptTaxes::None), Quantity::each(1));
+        receipt.add_item(item(2, 10_000, &liquor, ExemptTaxes::All), Quantity::each(1));
+        // The customer doesn't pay state or city tax, so this line isn't taxed either
+        receipt.add_item(item(3, 10_000, &state_and_city(), ExemptTaxes::Only(vec![2])), Quantity::each(1));
+        receipt.set_exemption(TaxExemption::new("Food Bank".to_string(), "EX-1".to_string(), vec![1, 2]));
+        let target = receipt.total();
+        assert_eq!(target, Money::from_cents(31_000));
+
+        let inference = infer_tax_rate(&receipt, target, Money::ZERO, &[]).unwrap();
+        assert_eq!(inference.suggested, "10".parse().unwrap());
+    }
+
+    #[test]
+    fn finds_a_receipt_with_a_partial_exemption() {
+        let mut receipt = Receipt::default();
+        receipt.add_item(item(1, 10_000, &state_and_city(), ExemptTaxes::Only(vec![2])), Quantity::each(1));
+        assert_eq!(receipt.calculate_total().total, Money::from_cents(10_400));
+
+        let solutions = solve(&receipt, Money::from_cents(10_400), Money::ZERO, SolveLimits::default()).unwrap();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].configuration.taxed_lines, vec![true]);
+    }
+
+    #[test]
+    fn searches_thirty_lines_with_an_exemption() {
+        let mut receipt = Receipt::default();
+        for id in 0..30 {
+            let exempt_taxes = if id % 3 == 0 { ExemptTaxes::Only(vec![2]) } else { ExemptTaxes::None };
+            receipt.add_item(item(id, 1_000 + id * 137, &state_and_city(), exempt_taxes), Quantity::each(1));
+        }
+        let target = receipt.calculate_total().total;
+
+        let solutions = solve(&receipt, target, Money::ZERO, SolveLimits::default()).unwrap();
+        assert!(solutions.iter().any(|solution| solution.configuration.taxed_lines.iter().all(|taxed| *taxed)));
+        assert!(solutions.iter().all(|solution| solution.total == target));
+    }
+
+    #[test]
+    fn stops_at_the_result_limit() {
+        let mut receipt = Receipt::default();
+        for id in 0..6 {
+            receipt.add_item(item(id, 1_000, &state_and_city(), ExemptTaxes::None), Quantity::each(1));
+        }
+        // Any three of the six lines taxed
+        let target = Money::from_cents(6_000 + 3 * 60);
+
+        assert_eq!(solve(&receipt, target, Money::ZERO, SolveLimits::default()).unwrap().len(), 20);
+        assert_eq!(solve(&receipt, target, Money::ZERO, SolveLimits { max_results: 4 }).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn keeps_the_exact_match_found_last() {
+        let one_percent = TaxGroup::new(1, "Sales".to_string(), vec![Tax::new(1, "Sales".to_string(), "1".parse().unwrap())]);
+        let mut receipt = Receipt::default();
+        for id in 0..5 {
+            receipt.add_item(item(id, 100, &one_percent, ExemptTaxes::None), Quantity::each(1));
+        }
+
+        // Every configuration is within tolerance, the untaxed ones are searched first
+        let solutions = solve(&receipt, Money::from_cents(505), Money::from_cents(5), SolveLimits { max_results: 3 }).unwrap();
+        assert_eq!(solutions.len(), 3);
+        assert_eq!(solutions[0].difference, Money::ZERO);
+        assert_eq!(solutions[0].configuration.taxed_lines, vec![true; 5]);
+        assert!(solutions[1..].iter().all(|solution| solution.difference == Money::from_cents(-1)));
+    }
+
+    #[test]
+    fn finds_every_match_when_a_gratuity_switches_on() {
+        let inclusive = TaxGroup::new(1, "VAT".to_string(), vec![Tax::new(1, "VAT".to_string(), "10".parse().unwrap())]);
+        let mut gratuity = Gratuity::new(1, "Large Party".to_string(), "20".parse().unwrap(), ChargeBase::Subtotal, 0, TaxGroup::new(2, "None".to_string(), Vec::new()), ExemptTaxes::None);
+        gratuity.rules.push(GratuityRule::new(GratuityCondition::Subtotal(Money::from_cents(2_800)), RuleBase::PreDiscount));
+        let mut receipt = Receipt::new(1, "Party".to_string(), Vec::new(), vec![gratuity]);
+        for id in 0..3 {
+            let mut line = item(id, 1_000, &inclusive, ExemptTaxes::None);
+            line.tax_inclusive = true;
+            receipt.add_item(line, Quantity::each(1));
+        }
+
+        let configurations: Vec<Configuration> = (0..16_u32).map(|taxed| Configuration {
+            taxed_lines: (0..3).map(|index| taxed & (1 << index) != 0).collect(),
+            taxed_service_charges: Vec::new(),
+            taxed_gratuities: vec![taxed & 8 != 0],
+        }).collect();
+        let totals: Vec<Money> = configurations.iter().map(|configuration| configuration.apply(&receipt).total()).collect();
+        // Backing the tax out of every line drops the subtotal under the threshold
+        assert_eq!(totals[7], Money::from_cents(3_000));
+        assert!(totals[3] > totals[7]);
+
+        for target in [totals[7], totals[3]] {
+            let solutions = solve(&receipt, target, Money::ZERO, SolveLimits::default()).unwrap();
+            let expected: Vec<&Configuration> = configurations.iter().zip(&totals)
+                .filter(|(_, total)| **total == target)
+                .map(|(configuration, _)| configuration)
+                .collect();
+
+            assert_eq!(solutions.len(), expected.len());
+            assert!(expected.iter().all(|configuration| solutions.iter().any(|solution| solution.configuration == **configuration)));
+        }
+    }
+
+    #[test]
+    fn leaves_the_modifiers_of_an_untaxed_line_untaxed() {
+        let mut receipt = Receipt::default();
+        receipt.add_item(item(1, 1_000, &state_and_city(), ExemptTaxes::None), Quantity::each(1));
+        receipt.add_modifier(0, Modifier::new(1, "Add Bacon".to_string(), Money::from_cents(200), state_and_city(), ExemptTaxes::None));
+
+        let solutions = solve(&receipt, Money::from_cents(1_200), Money::ZERO, SolveLimits::default()).unwrap();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].configuration.taxed_lines, vec![false]);
+
+        let solutions = solve(&receipt, Money::from_cents(1_272), Money::ZERO, SolveLimits::default()).unwrap();
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].configuration.taxed_lines, vec![true]);
+    }
+
+    #[test]
+    fn turns_down_too_many_charges() {
+        let gratuities = (0..MAX_CHARGES as i64 + 1).map(|id| Gratuity { id: id, ..Gratuity::default() }).collect();
+        let receipt = Receipt::new(1, "Banquet".to_string(), Vec::new(), gratuities);
+
+        assert_eq!(
+            solve(&receipt, Money::ZERO, Money::ZERO, SolveLimits::default()),
+            Err(SolveError::TooManyCharges { charges: MAX_CHARGES + 1, max: MAX_CHARGES })
+        );
+    }
+}
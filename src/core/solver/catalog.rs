@@ -0,0 +1,381 @@
+use crate::core::{
+    charge::ChargeValue,
+    discount::{DiscountTiming, DiscountValue},
+    items::Item,
+    money::{Money, RoundingMode, RATE_SCALE},
+    quantity::Quantity,
+    receipt::Receipt,
+    tax_group::TaxGroup,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchLimits {
+    // Most of any one item a match may order
+    pub max_quantity: i32,
+    pub max_results: usize,
+    // Most orders priced before the search gives up, a subtotal many orders
+    // add up to could otherwise take the search all day
+    pub max_priced: usize,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            max_quantity: 4,
+            max_results: 25,
+            max_priced: 20_000,
+        }
+    }
+}
+
+// An item and how much of it is ordered
+type OrderLine = (Item, Quantity);
+
+// An item with its taxed cost at every quantity, index 0 is none of it
+type Choice<'a> = (&'a Item, Vec<i64>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogMatch {
+    pub order: Vec<OrderLine>,
+    pub total: Money,
+    // Calculated total minus the target
+    pub difference: Money,
+}
+
+/// Finds orders from `catalog` whose receipt total lands within `tolerance` of `target`.
+///
+/// Orders are priced on a copy of `template` with its lines removed, so its
+/// charges and rounding policy apply. Each line's taxed cost is fixed by its
+/// item and quantity, so the search first marks every taxed subtotal the
+/// catalog can reach. Orders with the same subtotal can still total differently
+/// (an exempt item carries a percent charge on more of its price), so every
+/// order of a subtotal is priced, and only subtotals whose total can land in
+/// range are walked. Items that cost nothing are left out, and nothing is found
+/// when the discounts take all of the subtotal off, as then no subtotal is out
+/// of reach.
+pub fn match_catalog(
+    catalog: &[Item],
+    template: &Receipt,
+    target: Money,
+    tolerance: Money,
+    limits: SearchLimits,
+) -> Vec<CatalogMatch> {
+    if limits.max_quantity < 1 || limits.max_results == 0 || limits.max_priced == 0 {
+        return Vec::new();
+    }
+
+    let mut template = template.clone();
+    template.clear_order();
+
+    let choices: Vec<Choice> = catalog.iter()
+        .map(|item| {
+            let costs = (0..=limits.max_quantity)
+                .map(|quantity| template.price_line(item, &item.quantity(quantity as i64)).total().cents())
+                .collect::<Vec<_>>();
+            (item, costs)
+        })
+        .filter(|(_, costs)| costs[1] > 0)
+        .collect();
+
+    let bounds = TotalBounds::new(&template, &choices);
+    let most: i64 = choices.iter().map(|(_, costs)| costs[costs.len() - 1]).sum();
+    let Some(highest) = bounds.highest_subtotal(target + tolerance) else {
+        return Vec::new();
+    };
+    let limit = highest.min(most);
+
+    // reachable[i] holds every subtotal the first i items can add up to
+    let size = limit as usize + 1;
+    let mut reachable = vec![Bits::with_zero(size)];
+    for (_, costs) in &choices {
+        let previous = reachable.last().unwrap();
+        let mut next = previous.clone();
+        for cost in costs.iter().skip(1) {
+            next.or_shifted(previous, *cost as usize);
+        }
+        reachable.push(next);
+    }
+
+    let receipt_total = |order: &[OrderLine]| {
+        let mut receipt = template.clone();
+        for (item, quantity) in order {
+            receipt.add_item(item.clone(), *quantity);
+        }
+        receipt.total()
+    };
+
+    let mut matches = Vec::new();
+    let mut priced = 0;
+    let all_items = reachable.last().unwrap();
+
+    for subtotal in (0..size).rev().filter(|subtotal| all_items.get(*subtotal)) {
+        // Every lower subtotal totals less than this one can
+        if bounds.highest(subtotal as i64) < (target - tolerance).cents() {
+            break;
+        }
+
+        let finished = !collect_orders(&choices, &reachable, choices.len(), subtotal, &mut Vec::new(), &mut |order| {
+            priced += 1;
+            let total = receipt_total(order);
+            let difference = total - target;
+            if difference.abs() <= tolerance {
+                matches.push(CatalogMatch {
+                    order: order.to_vec(),
+                    total,
+                    difference,
+                });
+            }
+            matches.len() < limits.max_results && priced < limits.max_priced
+        });
+
+        if finished {
+            break;
+        }
+    }
+
+    matches.sort_by_key(|catalog_match| {
+        let item_count: i64 = catalog_match.order.iter().map(|(_, quantity)| quantity.thousandths()).sum();
+        (catalog_match.difference.abs(), item_count)
+    });
+
+    matches
+}
+
+// Walk back through the reachable sets, calling `found` for every order that adds up to `subtotal`.
+// Returns false once `found` asks to stop.
+fn collect_orders(
+    choices: &[Choice],
+    reachable: &[Bits],
+    count: usize,
+    subtotal: usize,
+    order: &mut Vec<OrderLine>,
+    found: &mut dyn FnMut(&[OrderLine]) -> bool,
+) -> bool {
+    if count == 0 {
+        return subtotal != 0 || found(order);
+    }
+
+    let (item, costs) = &choices[count - 1];
+    for (quantity, cost) in costs.iter().enumerate() {
+        let cost = *cost as usize;
+        if cost > subtotal || !reachable[count - 1].get(subtotal - cost) {
+            continue;
+        }
+
+        if quantity > 0 {
+            order.push(((*item).clone(), item.quantity(quantity as i64)));
+        }
+        let keep_going = collect_orders(choices, reachable, count - 1, subtotal - cost, order, found);
+        if quantity > 0 {
+            order.pop();
+        }
+
+        if !keep_going {
+            return false;
+        }
+    }
+
+    true
+}
+
+// How far a receipt's total can move from what its lines cost with their taxes, in cents.
+// Charges and the minimum spend only add to it, discounts only take off, and the
+// rest is rounding.
+struct TotalBounds {
+    charges: Vec<(ChargeValue, TaxGroup)>,
+    // The minimum spend and its taxes, the most a shortfall can add
+    minimum: i64,
+    guests: u32,
+    // Tips on the template's payments go on top unchanged
+    tips: i64,
+    // Every percent discount added up, in millionths
+    percent_off: i64,
+    // Fixed discounts with the most tax they can take off with them
+    fixed_off: i64,
+    // A cent for every rounded amount that can differ from the lines' own rounding
+    allowance: i64,
+}
+
+impl TotalBounds {
+    fn new(template: &Receipt, choices: &[Choice]) -> Self {
+        let on_date = |tax_group: &TaxGroup| match template.date() {
+            Some(date) => tax_group.on(date),
+            None => tax_group.clone(),
+        };
+        let tax_on = |tax_group: &TaxGroup, amount: Money| -> i64 {
+            let taxes = on_date(tax_group).calculate_taxes(amount, RoundingMode::HalfUp);
+            taxes.iter().map(|t| t.amount.cents()).sum::<i64>() + taxes.len() as i64
+        };
+        let groups = || choices.iter().flat_map(|(item, _)| {
+            std::iter::once(&item.tax_group).chain(item.modifiers.iter().map(|modifier| &modifier.tax_group))
+        });
+
+        let mut charges: Vec<(i32, ChargeValue, TaxGroup)> = template.service_charges().iter()
+            .map(|charge| (charge.order, charge.value, on_date(&charge.tax_group)))
+            .chain(template.gratuities().iter().map(|gratuity| (gratuity.order, ChargeValue::Percent(gratuity.rate), on_date(&gratuity.tax_group))))
+            .collect();
+        charges.sort_by_key(|(order, _, _)| *order);
+
+        let minimum = template.minimum_spend()
+            .map_or(0, |minimum_spend| minimum_spend.minimum.cents() + tax_on(&minimum_spend.tax_group, minimum_spend.minimum));
+
+        let mut percent_off = 0;
+        let mut fixed_off = 0;
+        for discount in template.discounts() {
+            match discount.value {
+                DiscountValue::Percent(rate) => percent_off += rate.millionths().max(0),
+                // A pre-tax discount takes the tax on what it takes off as well
+                DiscountValue::Fixed(amount) => fixed_off += amount.cents().max(0) + match discount.timing {
+                    DiscountTiming::PreTax => groups().map(|tax_group| tax_on(tax_group, amount)).max().unwrap_or(0),
+                    DiscountTiming::PostTax => 0,
+                },
+            }
+        }
+
+        let line_rounding: i64 = groups().map(|tax_group| tax_group.taxes.len() as i64 + 1).sum();
+        let charge_rounding: i64 = charges.iter().map(|(_, _, tax_group)| tax_group.taxes.len() as i64 + 1).sum();
+
+        TotalBounds {
+            charges: charges.into_iter().map(|(_, value, tax_group)| (value, tax_group)).collect(),
+            minimum: minimum,
+            guests: template.guests(),
+            tips: template.payments().iter().map(|payment| payment.tip.cents()).sum(),
+            percent_off: percent_off,
+            fixed_off: fixed_off,
+            // Lines are rounded again once per invoice and again for their share of a discount
+            allowance: 2 * line_rounding + charge_rounding + template.discounts().len() as i64 + 2,
+        }
+    }
+
+    // The most an order costing `subtotal` can total. Every charge base is at most
+    // the running total, so each charge is taken on that.
+    fn highest(&self, subtotal: i64) -> i64 {
+        let mut running = Money::from_cents(subtotal + self.allowance + self.minimum);
+        for (value, tax_group) in &self.charges {
+            let amount = value.amount(running, self.guests, RoundingMode::HalfUp).max(Money::ZERO) + Money::from_cents(1);
+            let tax: Money = tax_group.calculate_taxes(amount, RoundingMode::HalfUp).iter().map(|t| t.amount).sum();
+            running += amount + tax + Money::from_cents(tax_group.taxes.len() as i64);
+        }
+
+        running.cents() + self.tips
+    }
+
+    // The highest subtotal whose total can still come to `total` once every
+    // discount is taken off it, none when the discounts leave nothing to keep
+    fn highest_subtotal(&self, total: Money) -> Option<i64> {
+        let keep = RATE_SCALE - self.percent_off;
+        if keep <= 0 {
+            return None;
+        }
+
+        let most = (total.cents() - self.tips + self.fixed_off + self.allowance) as i128 * RATE_SCALE as i128 / keep as i128;
+        Some(most.clamp(0, i64::MAX as i128) as i64)
+    }
+}
+
+// Fixed size set of cent amounts
+#[derive(Debug, Clone)]
+struct Bits {
+    words: Vec<u64>,
+    size: usize,
+}
+
+impl Bits {
+    fn with_zero(size: usize) -> Self {
+        let mut words = vec![0; size.div_ceil(64)];
+        words[0] = 1;
+        Bits { words, size }
+    }
+
+    fn get(&self, index: usize) -> bool {
+        index < self.size && self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    // Add every amount in `other` moved up by `shift`, anything past the end is dropped
+    fn or_shifted(&mut self, other: &Bits, shift: usize) {
+        let word_shift = shift / 64;
+        let bit_shift = shift % 64;
+
+        for index in (word_shift..self.words.len()).rev() {
+            let source = index - word_shift;
+            let mut word = other.words[source] << bit_shift;
+            if bit_shift > 0 && source > 0 {
+                word |= other.words[source - 1] >> (64 - bit_shift);
+            }
+            self.words[index] |= word;
+        }
+
+        let extra = self.words.len() * 64 - self.size;
+        if extra > 0 {
+            let last = self.words.len() - 1;
+            self.words[last] &= u64::MAX >> extra;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::charge::ChargeBase;
+    use crate::core::discount::{Discount, DiscountTarget};
+    use crate::core::exemption::ExemptTaxes;
+    use crate::core::service_charge::ServiceCharge;
+    use crate::core::tax::Tax;
+
+    #[test]
+    fn prices_every_order_of_a_subtotal() {
+        let sales = TaxGroup::new(1, "Sales".to_string(), vec![Tax::new(1, "Sales".to_string(), "10".parse().unwrap())]);
+        let catalog = vec![
+            Item::new(1, "Taxed".to_string(), "Food".to_string(), Money::from_cents(1000), sales.clone()),
+            Item::new(2, "Exempt".to_string(), "Food".to_string(), Money::from_cents(1100), sales.clone()).with_exempt_taxes(ExemptTaxes::All),
+        ];
+        let mut template = Receipt::default();
+        template.add_service_charge(ServiceCharge::new(1, "Service".to_string(), ChargeValue::Percent("10".parse().unwrap()), ChargeBase::Subtotal, 0, TaxGroup::new(2, "None".to_string(), Vec::new()), ExemptTaxes::None));
+
+        // Both cost $11.00 with tax, but only the exempt one carries $1.10 of service charge
+        let matches = match_catalog(&catalog, &template, Money::from_cents(1210), Money::ZERO, SearchLimits::default());
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].order.len(), 1);
+        assert_eq!(matches[0].order[0].0.id, 2);
+    }
+
+    #[test]
+    fn finds_orders_priced_above_the_target_before_a_discount() {
+        let catalog = vec![
+            Item::new(1, "Wings".to_string(), "Food".to_string(), Money::from_cents(1500), TaxGroup::new(1, "None".to_string(), Vec::new())),
+        ];
+        let mut template = Receipt::default();
+        template.add_discount(Discount::new(1, "Happy Hour".to_string(), DiscountValue::Percent("50".parse().unwrap()), DiscountTarget::Receipt, DiscountTiming::PreTax));
+
+        let matches = match_catalog(&catalog, &template, Money::from_cents(1500), Money::ZERO, SearchLimits::default());
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].order[0].1, Quantity::each(2));
+    }
+
+    #[test]
+    fn finds_nothing_when_the_discounts_take_everything_off() {
+        let catalog = vec![
+            Item::new(1, "Wings".to_string(), "Food".to_string(), Money::from_cents(1500), TaxGroup::new(1, "None".to_string(), Vec::new())),
+        ];
+        let mut template = Receipt::default();
+        for id in 1..=2 {
+            template.add_discount(Discount::new(id, "Half Off".to_string(), DiscountValue::Percent("50".parse().unwrap()), DiscountTarget::Receipt, DiscountTiming::PreTax));
+        }
+
+        assert!(match_catalog(&catalog, &template, Money::ZERO, Money::ZERO, SearchLimits::default()).is_empty());
+    }
+
+    #[test]
+    fn stops_after_pricing_the_most_orders_allowed() {
+        let none = TaxGroup::new(1, "None".to_string(), Vec::new());
+        // Every item costs the same, so many orders share each subtotal
+        let catalog: Vec<Item> = (1..=6)
+            .map(|id| Item::new(id, format!("Item {}", id), "Food".to_string(), Money::from_cents(100), none.clone()))
+            .collect();
+        let limits = SearchLimits { max_priced: 10, ..SearchLimits::default() };
+
+        let everything = match_catalog(&catalog, &Receipt::default(), Money::from_cents(400), Money::ZERO, SearchLimits::default());
+        assert_eq!(everything.len(), SearchLimits::default().max_results);
+        assert_eq!(match_catalog(&catalog, &Receipt::default(), Money::from_cents(400), Money::ZERO, limits).len(), 10);
+    }
+}
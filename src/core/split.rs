@@ -0,0 +1,327 @@
+use crate::core::{
+    breakdown::{ChargeKind, RoundingReason},
+    money::Money,
+    receipt::Receipt,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SplitError {
+    #[error("a check has to be split between at least one guest")]
+    NoGuests,
+    #[error("the plan covers {plan} lines but the receipt has {receipt}")]
+    LineCount { plan: usize, receipt: usize },
+    #[error("line {0} isn't assigned to anyone")]
+    UnassignedLine(usize),
+    #[error("guest {0} isn't part of the split")]
+    UnknownGuest(usize),
+}
+
+/// Who pays for each line of a receipt.
+///
+/// Guests are numbered from 0. A line can be shared by several guests, each
+/// taking a whole number of parts, so two parts to one guest and one to
+/// another splits that line two thirds and one third.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPlan {
+    pub guests: usize,
+    // For every line, the guests sharing it and how many parts each takes
+    pub lines: Vec<Vec<(usize, u32)>>,
+}
+
+impl SplitPlan {
+    pub fn new(guests: usize, line_count: usize) -> Self {
+        SplitPlan {
+            guests: guests,
+            lines: vec![Vec::new(); line_count],
+        }
+    }
+
+    // Every guest takes an equal part of every line
+    pub fn evenly(guests: usize, line_count: usize) -> Self {
+        SplitPlan {
+            guests: guests,
+            lines: vec![(0..guests).map(|guest| (guest, 1)).collect(); line_count],
+        }
+    }
+
+    pub fn assign(&mut self, line: usize, guest: usize) {
+        self.share(line, &[(guest, 1)]);
+    }
+
+    pub fn share(&mut self, line: usize, parts: &[(usize, u32)]) {
+        if let Some(shares) = self.lines.get_mut(line) {
+            *shares = parts.to_vec();
+        }
+    }
+}
+
+// What one guest owes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GuestShare {
+    pub guest: usize,
+    pub subtotal: Money,
+    pub discount: Money,
+    pub tax: Money,
+    // The part of `tax` already inside tax-inclusive prices
+    pub included_tax: Money,
+    pub service_charge: Money,
+    pub gratuity: Money,
+    // Part of what the receipt fell short of its minimum spend
+    pub shortfall: Money,
+    pub rounding: Money,
+    pub total: Money,
+}
+
+/// Splits a receipt's total between guests following `plan`.
+///
+/// Each line's price, discount and taxes go to the guests sharing it by their
+/// parts. Charges, charge taxes and rounding adjustments belong to the whole
+/// receipt and are shared in proportion to each guest's part of the lines.
+/// Every amount is split on its own so the guests' shares add back up to it
+/// exactly. Cents that can't be split evenly go to the guests with the largest
+/// remainders, then to the guest whose total the spare cents so far have
+/// pushed least in that direction, then to the lowest guest number. An even
+/// split never leaves two guests more than a cent apart.
+pub fn split(receipt: &Receipt, plan: &SplitPlan) -> Result<Vec<GuestShare>, SplitError> {
+    if plan.guests == 0 {
+        return Err(SplitError::NoGuests);
+    }
+
+    let breakdown = receipt.calculate_total();
+    if plan.lines.len() != breakdown.lines.len() {
+        return Err(SplitError::LineCount { plan: plan.lines.len(), receipt: breakdown.lines.len() });
+    }
+
+    for (index, shares) in plan.lines.iter().enumerate() {
+        if shares.iter().all(|(_, parts)| *parts == 0) {
+            return Err(SplitError::UnassignedLine(index));
+        }
+        if let Some((guest, _)) = shares.iter().find(|(guest, _)| *guest >= plan.guests) {
+            return Err(SplitError::UnknownGuest(*guest));
+        }
+    }
+
+    let mut pennies = Pennies::new(plan.guests);
+    let mut shares: Vec<GuestShare> = (0..plan.guests)
+        .map(|guest| GuestShare { guest: guest, ..GuestShare::default() })
+        .collect();
+
+    let line_weights: Vec<Vec<i128>> = plan.lines.iter().map(|line_shares| {
+        let mut weights = vec![0; plan.guests];
+        for (guest, parts) in line_shares {
+            weights[*guest] += *parts as i128;
+        }
+        weights
+    }).collect();
+
+    for (line, weights) in breakdown.lines.iter().zip(&line_weights) {
+        for (share, amount) in shares.iter_mut().zip(pennies.allocate(line.gross_price(), weights, 1)) {
+            share.subtotal += amount;
+        }
+        for (share, amount) in shares.iter_mut().zip(pennies.allocate(line.discount(), weights, -1)) {
+            share.discount += amount;
+        }
+        for (share, amount) in shares.iter_mut().zip(pennies.allocate(line.tax() - line.included_tax(), weights, 1)) {
+            share.tax += amount;
+        }
+        // Included tax is already inside the price, so it doesn't move the total
+        for (share, amount) in shares.iter_mut().zip(pennies.allocate(line.included_tax(), weights, 0)) {
+            share.tax += amount;
+            share.included_tax += amount;
+        }
+    }
+
+    // Everything not on a line follows each guest's exact part of the line totals,
+    // scaled so every line's parts come out whole
+    let scale = line_weights.iter().map(|weights| weights.iter().sum::<i128>()).fold(1, lcm);
+    let weights: Vec<i128> = (0..plan.guests).map(|guest| {
+        breakdown.lines.iter().zip(&line_weights)
+            .map(|(line, weights)| line.total().cents().max(0) as i128 * weights[guest] * (scale / weights.iter().sum::<i128>()))
+            .sum()
+    }).collect();
+
+    for charge in &breakdown.charges {
+        for (share, amount) in shares.iter_mut().zip(pennies.allocate(charge.amount, &weights, 1)) {
+            match charge.kind {
+                ChargeKind::ServiceCharge => share.service_charge += amount,
+                ChargeKind::Gratuity => share.gratuity += amount,
+                ChargeKind::MinimumSpend => share.shortfall += amount,
+            }
+        }
+        let charge_tax: Money = charge.taxes.iter().map(|t| t.amount).sum();
+        for (share, amount) in shares.iter_mut().zip(pennies.allocate(charge_tax, &weights, 1)) {
+            share.tax += amount;
+        }
+    }
+
+    for adjustment in &breakdown.rounding {
+        for (share, amount) in shares.iter_mut().zip(pennies.allocate(adjustment.amount, &weights, 1)) {
+            match adjustment.reason {
+                // Per-invoice tax adjustments are already part of the receipt's tax
+                RoundingReason::InvoiceTax(_) => share.tax += amount,
+                RoundingReason::Cash => share.rounding += amount,
+            }
+        }
+    }
+
+    for share in &mut shares {
+        share.total = share.subtotal - share.discount + share.tax - share.included_tax
+            + share.service_charge + share.gratuity + share.shortfall + share.rounding;
+    }
+
+    debug_assert_eq!(shares.iter().map(|share| share.total).sum::<Money>(), breakdown.total);
+
+    Ok(shares)
+}
+
+// Every guest pays the same, give or take a cent
+pub fn split_evenly(receipt: &Receipt, guests: usize) -> Result<Vec<GuestShare>, SplitError> {
+    split(receipt, &SplitPlan::evenly(guests, receipt.order().len()))
+}
+
+// Hands out spare cents, keeping track of how far each guest's total has been pushed by them
+struct Pennies {
+    balance: Vec<i64>,
+}
+
+impl Pennies {
+    fn new(guests: usize) -> Self {
+        Pennies { balance: vec![0; guests] }
+    }
+
+    // Split `amount` by `weights`, with no weight at all it is split evenly.
+    // `effect` is what one cent of the amount does to a guest's total.
+    fn allocate(&mut self, amount: Money, weights: &[i128], effect: i64) -> Vec<Money> {
+        let weights: Vec<i128> = if weights.iter().all(|weight| *weight == 0) {
+            vec![1; weights.len()]
+        } else {
+            weights.to_vec()
+        };
+        let total_weight: i128 = weights.iter().sum();
+        let cents = amount.cents() as i128;
+
+        let mut shares: Vec<i64> = weights.iter().map(|weight| (cents * weight / total_weight) as i64).collect();
+        let leftover = amount.cents() - shares.iter().sum::<i64>();
+        let step = if leftover < 0 { -1 } else { 1 };
+        let push = step * effect;
+
+        // Largest remainder first, then the guest a cent moves least out of line with the others
+        let mut order: Vec<usize> = (0..weights.len()).filter(|guest| weights[*guest] > 0).collect();
+        order.sort_by_key(|guest| {
+            let remainder = (cents * weights[*guest] % total_weight).abs();
+            (std::cmp::Reverse(remainder), self.balance[*guest] * push, *guest)
+        });
+
+        for guest in order.iter().take(leftover.unsigned_abs() as usize) {
+            shares[*guest] += step;
+            self.balance[*guest] += push;
+        }
+
+        shares.into_iter().map(Money::from_cents).collect()
+    }
+}
+
+fn lcm(a: i128, b: i128) -> i128 {
+    let gcd = |mut a: i128, mut b: i128| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+
+    a / gcd(a, b) * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::charge::{ChargeBase, ChargeValue};
+    use crate::core::discount::{Discount, DiscountTarget, DiscountTiming, DiscountValue};
+    use crate::core::exemption::ExemptTaxes;
+    use crate::core::items::Item;
+    use crate::core::minimum_spend::MinimumSpend;
+    use crate::core::quantity::Quantity;
+    use crate::core::rounding::{CashRounding, RoundingPolicy, TaxRounding};
+    use crate::core::service_charge::ServiceCharge;
+    use crate::core::tax::Tax;
+    use crate::core::tax_group::TaxGroup;
+
+    fn receipt() -> Receipt {
+        let sales = TaxGroup::new(1, "Sales".to_string(), vec![Tax::new(1, "Sales".to_string(), "8.875".parse().unwrap())]);
+        let mut receipt = Receipt::default();
+        for (id, cents) in [1_099, 2_345, 799, 1_001].into_iter().enumerate() {
+            let item = Item::new(id as i64, format!("Item {}", id), "Food".to_string(), Money::from_cents(cents), sales.clone());
+            receipt.add_item(item, Quantity::each(1));
+        }
+        receipt.add_service_charge(ServiceCharge::new(1, "Service".to_string(), ChargeValue::Percent("18".parse().unwrap()), ChargeBase::Subtotal, 0, sales.clone(), ExemptTaxes::None));
+        receipt.add_discount(Discount::new(1, "Regular".to_string(), DiscountValue::Fixed(Money::from_cents(333)), DiscountTarget::Receipt, DiscountTiming::PostTax));
+        receipt.set_minimum_spend(MinimumSpend::new("F&B Minimum".to_string(), Money::from_cents(6_000), Vec::new(), sales));
+        receipt.set_rounding_policy(RoundingPolicy::new(TaxRounding::default(), CashRounding::Nickel));
+        receipt
+    }
+
+    fn assert_adds_up(receipt: &Receipt, shares: &[GuestShare]) {
+        let breakdown = receipt.calculate_total();
+        let sum = |part: fn(&GuestShare) -> Money| shares.iter().map(part).sum::<Money>();
+
+        assert_eq!(sum(|share| share.total), breakdown.total);
+        assert_eq!(sum(|share| share.subtotal), breakdown.subtotal);
+        assert_eq!(sum(|share| share.service_charge), breakdown.service_charge());
+        assert_eq!(sum(|share| share.shortfall), breakdown.shortfall());
+    }
+
+    #[test]
+    fn even_shares_add_up_to_the_receipt() {
+        let receipt = receipt();
+        for guests in 1..=7 {
+            assert_adds_up(&receipt, &split_evenly(&receipt, guests).unwrap());
+        }
+    }
+
+    #[test]
+    fn even_shares_stay_within_a_cent_of_each_other() {
+        let sales = TaxGroup::new(1, "Sales".to_string(), vec![Tax::new(1, "Sales".to_string(), "8.875".parse().unwrap())]);
+        let mut receipts = vec![receipt()];
+        for prices in [vec![1], vec![3_333, 1], vec![999, 1_001, 1_003], vec![12_345, 67, 8_901, 2]] {
+            let mut receipt = receipt();
+            for (id, cents) in prices.into_iter().enumerate() {
+                let item = Item::new(10 + id as i64, format!("Extra {}", id), "Food".to_string(), Money::from_cents(cents), sales.clone());
+                receipt.add_item(item, Quantity::each(1));
+            }
+            receipts.push(receipt);
+        }
+
+        for receipt in &receipts {
+            for guests in 2..=11 {
+                let totals: Vec<Money> = split_evenly(receipt, guests).unwrap().iter().map(|share| share.total).collect();
+                let spread = *totals.iter().max().unwrap() - *totals.iter().min().unwrap();
+                assert!(spread <= Money::from_cents(1), "{} guests of {} are {} apart", guests, receipt.total(), spread);
+            }
+        }
+    }
+
+    #[test]
+    fn seat_and_shared_lines_add_up_to_the_receipt() {
+        let receipt = receipt();
+        let mut plan = SplitPlan::new(3, receipt.order().len());
+        for (line, seat) in [0, 1, 2, 0].into_iter().enumerate() {
+            plan.assign(line, seat);
+        }
+        assert_adds_up(&receipt, &split(&receipt, &plan).unwrap());
+
+        plan.share(3, &[(0, 2), (1, 1)]);
+        assert_adds_up(&receipt, &split(&receipt, &plan).unwrap());
+    }
+
+    #[test]
+    fn turns_down_plans_that_miss_a_line() {
+        let receipt = receipt();
+        let mut plan = SplitPlan::new(2, receipt.order().len());
+        for (line, seat) in [(0, 0), (1, 1), (3, 1)] {
+            plan.assign(line, seat);
+        }
+
+        assert!(matches!(split(&receipt, &plan), Err(SplitError::UnassignedLine(2))));
+        assert!(matches!(split_evenly(&receipt, 0), Err(SplitError::NoGuests)));
+    }
+}
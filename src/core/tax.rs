@@ -1,28 +1,160 @@
+use std::fmt;
+
 use iced::widget::{button,column, row, text, text_input, Container};
 use iced::{Color, Element, Length};
-use std::hash::{Hash, Hasher};
 use serde::{Deserialize, Serialize};
 
+use crate::core::date::Date;
+use crate::core::money::Rate;
 use crate::ui::custom_appearances::{pos_table_header, pos_table_row};
 use crate::ui::tax_list_view::{Message, TaxEditState};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, PartialOrd)]
+// What a tax inside a tax group is charged on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd)]
+pub enum TaxBase {
+    #[default]
+    Price,
+    // The price plus every tax with a lower order in the same group
+    PriceAndEarlierTaxes,
+}
+
+impl TaxBase {
+    pub const ALL: [TaxBase; 2] = [TaxBase::Price, TaxBase::PriceAndEarlierTaxes];
+}
+
+impl fmt::Display for TaxBase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TaxBase::Price => "Price",
+            TaxBase::PriceAndEarlierTaxes => "Price and earlier taxes",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A rate that was in force from one day to another, both days included.
+// An open end runs as far back or forward as there are no other records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd)]
+pub struct TaxRate {
+    pub rate: Rate,
+    pub from: Option<Date>,
+    pub to: Option<Date>,
+}
+
+impl TaxRate {
+    pub fn new(rate: Rate, from: Option<Date>, to: Option<Date>) -> Self {
+        TaxRate {
+            rate: rate,
+            from: from,
+            to: to,
+        }
+    }
+
+    pub fn covers(&self, date: Date) -> bool {
+        self.from.is_none_or(|from| from <= date) && self.to.is_none_or(|to| date <= to)
+    }
+}
+
+impl fmt::Display for TaxRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let from = self.from.map_or("the start".to_string(), |from| from.to_string());
+        let to = self.to.map_or("now".to_string(), |to| to.to_string());
+        write!(f, "{} from {} to {}", self.rate, from, to)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd)]
 pub struct Tax {
     pub id: i64,
     pub name: String,
-    pub percent: f64,
+    // The rate in force now
+    #[serde(alias = "percent")]
+    pub rate: Rate,
+    // Rates in force on other dates, any day not covered uses the current rate
+    #[serde(default)]
+    pub history: Vec<TaxRate>,
+    // Position of the tax in its tax group, lower orders are charged first
+    #[serde(default)]
+    pub order: i32,
+    #[serde(default)]
+    pub base: TaxBase,
 }
 
 impl Tax {
-    pub fn new(id: i64, name: String, percent: f64, ) -> Self {
-        let tax_as_percentage = percent * 100.0;
+    pub fn new(id: i64, name: String, rate: Rate, ) -> Self {
         
         Tax {
             id: id,
             name: name,
-            percent: percent,
+            rate: rate,
+            history: Vec::new(),
+            order: 0,
+            base: TaxBase::Price,
         }
     }
+
+    // A day covered by more than one record takes the one that started latest
+    pub fn rate_on(&self, date: Date) -> Rate {
+        self.history.iter()
+            .filter(|period| period.covers(date))
+            .max_by_key(|period| period.from)
+            .map_or(self.rate, |period| period.rate)
+    }
+
+    // The same tax charged at the rate in force on `date`
+    pub fn on(&self, date: Date) -> Tax {
+        Tax {
+            rate: self.rate_on(date),
+            ..self.clone()
+        }
+    }
+
+    // The first day of the current rate, if the history says
+    pub fn current_from(&self) -> Option<Date> {
+        self.history.iter().filter_map(|period| period.to).max().map(|to| to.next_day())
+    }
+
+    // Charge `rate` from `from` on until the next recorded change. The period it
+    // lands in is cut short at the day before, so a backdated rate only replaces
+    // the days up to the change after it and a later rate stays the current one.
+    pub fn set_rate(&mut self, rate: Rate, from: Date) {
+        let mut periods = self.rate_history();
+        periods.push(TaxRate::new(self.rate, self.current_from(), None));
+
+        match periods.iter().rposition(|period| period.covers(from)) {
+            Some(index) if periods[index].from == Some(from) => periods[index].rate = rate,
+            Some(index) => {
+                let to = periods[index].to;
+                periods[index].to = Some(from.previous_day());
+                periods.insert(index + 1, TaxRate::new(rate, Some(from), to));
+            }
+            // A day before the first record or in a gap between two, filled up to the next one
+            None => {
+                let index = periods.iter().position(|period| period.from > Some(from)).unwrap_or(periods.len());
+                let to = periods.get(index).and_then(|next| next.from).map(|next| next.previous_day());
+                periods.insert(index, TaxRate::new(rate, Some(from), to));
+            }
+        }
+
+        // Back to back periods at the same rate are one period
+        let mut merged: Vec<TaxRate> = Vec::new();
+        for period in periods {
+            match merged.last_mut() {
+                Some(last) if last.rate == period.rate && last.to.map(|to| to.next_day()) == period.from => last.to = period.to,
+                _ => merged.push(period),
+            }
+        }
+
+        self.rate = merged.pop().map_or(rate, |current| current.rate);
+        self.history = merged;
+    }
+
+    // Earlier rates first
+    pub fn rate_history(&self) -> Vec<TaxRate> {
+        let mut history = self.history.clone();
+        history.sort_by_key(|period| (period.from, period.to));
+        history
+    }
 }
 
 impl Default for Tax {
@@ -30,31 +162,23 @@ impl Default for Tax {
         Self {
             id: 1,
             name: "default".to_string(),
-            percent: 7.75, 
+            rate: Rate::from_basis_points(775), 
+            history: Vec::new(),
+            order: 0,
+            base: TaxBase::Price,
         }
     }
 }
 
-impl Eq for Tax {}
-
-impl Hash for Tax {
-    fn hash<H: Hasher>(&self, state: &mut H){
-        self.id.hash(state);
-        self.name.hash(state);
-
-        let percent_bits = self.percent.to_bits();
-        percent_bits.hash(state);
-    }
-}
-
 
 pub fn create_taxes_table(taxes: Vec<Tax>, edit_states: &mut std::collections::HashMap<i64, TaxEditState>) -> Element<'static, Message> {
     // Table header
     let header = row![
         table_cell("ID".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
         table_cell("Name".to_string(), true, 200_f32, 0, false, "".to_string(), edit_states),
-        table_cell("Percent".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
-        table_cell("Actions".to_string(), true, 200_f32, 0, false, "".to_string(), edit_states)
+        table_cell("Current Rate".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Effective From".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Actions".to_string(), true, 300_f32, 0, false, "".to_string(), edit_states)
     ]
     .spacing(1)
     .padding(2)
@@ -72,10 +196,11 @@ pub fn create_taxes_table(taxes: Vec<Tax>, edit_states: &mut std::collections::H
                     row![
                     table_cell(tax.id.to_string(), false, 100_f32, tax.id, false, "id".to_string(), edit_states),
                     table_cell(tax.name.clone(), false, 200_f32, tax.id, is_editing, "name".to_string(), edit_states),
-                    table_cell(format!("{:.2}%", tax.percent), false, 100_f32, tax.id, is_editing, "percent".to_string(), edit_states),
+                    table_cell(format!("{:.2}", tax.rate), false, 100_f32, tax.id, is_editing, "percent".to_string(), edit_states),
+                    table_cell(tax.current_from().map_or("-".to_string(), |from| from.to_string()), false, 100_f32, tax.id, is_editing, "from".to_string(), edit_states),
                     table_cell_with_action(
                         tax.id, 
-                        210_f32,
+                        310_f32,
                         is_editing)
                     ].spacing(1)
                     .padding(2)
@@ -126,6 +251,7 @@ pub fn table_cell<'a>(
                 match field_name.as_str() {
                     "name" => edit_state.name.clone(),
                     "percent" => edit_state.percent.clone(),
+                    "from" => edit_state.from.clone(),
                     _ => content.clone()
                 }
             } else {
@@ -184,10 +310,101 @@ pub fn table_cell_with_action(tax_id: i64, width: f32, is_editing: bool) -> Elem
             .padding(2)
     };
 
-    row![edit_save_button, delete_cancel_button]
+    let history_button = button("History")
+        .on_press(Message::ShowHistory(tax_id))
+        .width(Length::Fill)
+        .padding(2);
+
+    row![edit_save_button, delete_cancel_button, history_button]
         .spacing(1)
         .width(Length::Fixed(width))
         .align_y(iced::Alignment::Center)
         .into()
 
-}
\ No newline at end of file
+}
+
+// Every rate a tax has had, oldest first, ending with the current one
+pub fn create_rate_history(tax: &Tax) -> Element<'static, Message> {
+    let current = TaxRate::new(tax.rate, tax.current_from(), None);
+
+    column(
+        std::iter::once(text(format!("{} rate history", tax.name)).size(16).into())
+            .chain(
+                tax.rate_history().into_iter().chain(std::iter::once(current)).map(|period| {
+                    text(period.to_string()).size(12).into()
+                })
+            )
+            .collect::<Vec<_>>(),
+    )
+    .spacing(4)
+    .padding(5)
+    .into()
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        text.parse().unwrap()
+    }
+
+    fn rate(text: &str) -> Rate {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn a_new_rate_keeps_the_old_one_in_the_history() {
+        let mut tax = Tax::new(1, "Sales".to_string(), rate("7"));
+        tax.set_rate(rate("7.5"), date("2024-01-01"));
+
+        assert_eq!(tax.rate, rate("7.5"));
+        assert_eq!(tax.current_from(), Some(date("2024-01-01")));
+        assert_eq!(tax.rate_on(date("2023-12-31")), rate("7"));
+        assert_eq!(tax.rate_on(date("2024-01-01")), rate("7.5"));
+    }
+
+    #[test]
+    fn a_backdated_rate_runs_up_to_the_next_change() {
+        let mut tax = Tax::new(1, "Sales".to_string(), rate("7"));
+        tax.set_rate(rate("7.5"), date("2024-01-01"));
+        tax.set_rate(rate("8"), date("2025-01-01"));
+        tax.set_rate(rate("9"), date("2024-06-01"));
+
+        assert_eq!(tax.rate, rate("8"));
+        assert_eq!(tax.current_from(), Some(date("2025-01-01")));
+        assert_eq!(tax.rate_on(date("2023-12-31")), rate("7"));
+        assert_eq!(tax.rate_on(date("2024-05-31")), rate("7.5"));
+        assert_eq!(tax.rate_on(date("2024-07-01")), rate("9"));
+        assert_eq!(tax.rate_on(date("2024-12-31")), rate("9"));
+        assert_eq!(tax.rate_on(date("2025-01-01")), rate("8"));
+        assert_eq!(tax.rate_history(), vec![
+            TaxRate::new(rate("7"), None, Some(date("2023-12-31"))),
+            TaxRate::new(rate("7.5"), Some(date("2024-01-01")), Some(date("2024-05-31"))),
+            TaxRate::new(rate("9"), Some(date("2024-06-01")), Some(date("2024-12-31"))),
+        ]);
+    }
+
+    #[test]
+    fn a_rate_from_the_start_of_a_period_replaces_it() {
+        let mut tax = Tax::new(1, "Sales".to_string(), rate("7"));
+        tax.set_rate(rate("7.5"), date("2024-01-01"));
+        tax.set_rate(rate("8"), date("2025-01-01"));
+        tax.set_rate(rate("8"), date("2024-01-01"));
+
+        assert_eq!(tax.rate, rate("8"));
+        assert_eq!(tax.current_from(), Some(date("2024-01-01")));
+        assert_eq!(tax.rate_history(), vec![TaxRate::new(rate("7"), None, Some(date("2023-12-31")))]);
+    }
+
+    #[test]
+    fn the_latest_record_wins_where_old_records_overlap() {
+        let mut tax = Tax::new(1, "Sales".to_string(), rate("8"));
+        tax.history = vec![
+            TaxRate::new(rate("7.5"), Some(date("2024-01-01")), Some(date("2024-12-31"))),
+            TaxRate::new(rate("7"), None, Some(date("2024-03-31"))),
+        ];
+
+        assert_eq!(tax.rate_on(date("2024-02-01")), rate("7.5"));
+        assert_eq!(tax.rate_on(date("2023-02-01")), rate("7"));
+    }
+}
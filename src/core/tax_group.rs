@@ -1,8 +1,12 @@
-use iced::widget::{button, column, row, text, text_input, Column, Container};
+use iced::widget::{button, column, pick_list, row, text, text_input, Column, Container};
 use iced::{Color, Element, Length};
 
-use crate::core::tax::Tax;
-use crate::ui::custom_appearances::{pos_table_header, pos_table_row, multiple_items};
+use crate::core::date::Date;
+use crate::core::money::{Money, Rate, RoundingMode};
+use crate::core::rounding::{GroupRounding, TaxRounding};
+use crate::core::tax::{Tax, TaxBase};
+use crate::core::breakdown::TaxAmount;
+use crate::ui::custom_appearances::{pos_table_header, pos_table_row};
 use crate::ui::tax_group_list_view::{Message, TaxGroupEditState};
 
 
@@ -13,6 +17,10 @@ pub struct TaxGroup {
     pub id: i64,
     pub name: String,
     pub taxes: Vec<Tax>,
+    // Overrides the receipt's tax rounding for taxes charged through this group
+    pub rounding: Option<TaxRounding>,
+    // Prices charged through this group already include its taxes
+    pub tax_inclusive: bool,
 }
 
 impl TaxGroup {
@@ -20,20 +28,100 @@ impl TaxGroup {
         TaxGroup {
             id: id,
             name: name,
-            taxes: taxes
+            taxes: taxes,
+            rounding: None,
+            tax_inclusive: false,
         }
     }
     
-    pub fn get_tax_percent(&self, tax_name: &str) -> Option<f64> {
+    pub fn get_tax_percent(&self, tax_name: &str) -> Option<Rate> {
         // Search for the tax by name and return its percent if found
         for tax in &self.taxes {
             if tax.name == tax_name {
-                return Some(tax.percent);
+                return Some(tax.rate);
             }
         }
         None
     }
 
+    // The same group charging the rates that were in force on `date`
+    pub fn on(&self, date: Date) -> TaxGroup {
+        TaxGroup {
+            taxes: self.taxes.iter().map(|tax| tax.on(date)).collect(),
+            ..self.clone()
+        }
+    }
+
+    // The same group without the given taxes
+    pub fn without(&self, tax_ids: &[i64]) -> TaxGroup {
+        TaxGroup {
+            taxes: self.taxes.iter().filter(|tax| !tax_ids.contains(&tax.id)).cloned().collect(),
+            ..self.clone()
+        }
+    }
+
+    // Taxes in charging order, ties keep the order they were added in
+    pub fn ordered_taxes(&self) -> Vec<&Tax> {
+        let mut taxes: Vec<&Tax> = self.taxes.iter().collect();
+        taxes.sort_by_key(|tax| tax.order);
+        taxes
+    }
+
+    pub fn calculate_taxes(&self, amount: Money, rounding: RoundingMode) -> Vec<TaxAmount> {
+        let mut tax_amounts: Vec<TaxAmount> = Vec::new();
+
+        for tax in self.ordered_taxes() {
+            let taxable = match tax.base {
+                TaxBase::Price => amount,
+                TaxBase::PriceAndEarlierTaxes => amount + tax_amounts.iter()
+                    .filter(|earlier| earlier.tax.order < tax.order)
+                    .map(|earlier| earlier.amount)
+                    .sum::<Money>(),
+            };
+
+            tax_amounts.push(TaxAmount {
+                tax: tax.clone(),
+                taxable: taxable,
+                amount: taxable.apply_rate(tax.rate, rounding),
+            });
+        }
+
+        tax_amounts
+    }
+
+    /// Backs the taxes out of a price that already includes them.
+    ///
+    /// The net is the largest amount whose taxes, added on top, don't go over
+    /// `gross`. Those taxes are returned and the net is whatever is left, so the
+    /// two always add back up to the gross exactly.
+    pub fn calculate_included_taxes(&self, gross: Money, rounding: RoundingMode) -> Vec<TaxAmount> {
+        if gross.is_negative() {
+            let mut tax_amounts = self.calculate_included_taxes(-gross, rounding);
+            for tax_amount in &mut tax_amounts {
+                tax_amount.taxable = -tax_amount.taxable;
+                tax_amount.amount = -tax_amount.amount;
+            }
+            return tax_amounts;
+        }
+
+        let gross_of = |net: i64| {
+            let net = Money::from_cents(net);
+            net + self.calculate_taxes(net, rounding).iter().map(|t| t.amount).sum::<Money>()
+        };
+
+        // Adding tax on top never lowers the gross, so the net can be binary searched
+        let (mut low, mut high) = (0, gross.cents());
+        while low < high {
+            let middle = low + (high - low + 1) / 2;
+            if gross_of(middle) <= gross {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+
+        self.calculate_taxes(Money::from_cents(low), rounding)
+    }
 }
 
 impl Default for TaxGroup {
@@ -44,7 +132,9 @@ impl Default for TaxGroup {
         Self {
             id: 1,
             name: "Default".to_string(),
-            taxes: tax_vec
+            taxes: tax_vec,
+            rounding: None,
+            tax_inclusive: false,
         }
     }
 }
@@ -54,7 +144,9 @@ pub fn create_tax_group_table(tax_groups: Vec<TaxGroup>, edit_states: &mut std::
     let header = row![
         table_cell("ID".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
         table_cell("Name".to_string(), true, 200_f32, 0, false, "".to_string(), edit_states),
-        table_cell("Taxes".to_string(), true, 100_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Taxes".to_string(), true, 440_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Inclusive".to_string(), true, 75_f32, 0, false, "".to_string(), edit_states),
+        table_cell("Rounding".to_string(), true, 130_f32, 0, false, "".to_string(), edit_states),
         table_cell("Actions".to_string(), true, 200_f32, 0, false, "".to_string(), edit_states)
     ]
     .spacing(1)
@@ -73,7 +165,15 @@ pub fn create_tax_group_table(tax_groups: Vec<TaxGroup>, edit_states: &mut std::
                     row![
                     table_cell(tax_group.id.to_string(), false, 100_f32, tax_group.id, false, "id".to_string(), edit_states),
                     table_cell(tax_group.name.clone(), false, 200_f32, tax_group.id, is_editing, "name".to_string(), edit_states),
-                    table_cell(taxes_to_string(tax_group.taxes), false, 100_f32, tax_group.id, is_editing, "price".to_string(), edit_states),
+                    match edit_states.get(&tax_group.id).filter(|_| is_editing) {
+                        Some(edit_state) => taxes_cell(tax_group.id, 440_f32, edit_state),
+                        None => table_cell(taxes_to_string(tax_group.taxes), false, 440_f32, tax_group.id, false, "taxes".to_string(), edit_states),
+                    },
+                    table_cell(tax_group.tax_inclusive.to_string(), false, 75_f32, 0, false, "tax_inclusive".to_string(), edit_states),
+                    match edit_states.get(&tax_group.id).filter(|_| is_editing) {
+                        Some(edit_state) => rounding_cell(tax_group.id, 130_f32, edit_state),
+                        None => table_cell(GroupRounding::from(tax_group.rounding).to_string(), false, 130_f32, tax_group.id, false, "rounding".to_string(), edit_states),
+                    },
                     //table_cell(taxes_to_string(tax_group.taxes), false, 100_f32, tax_group.id, is_editing, "price".to_string(), edit_states),
                     table_cell_with_action(
                         tax_group.id, 
@@ -126,11 +226,6 @@ pub fn table_cell<'a>(
             let current_value = if let Some(edit_state) = edit_states.get(&tax_group_id) {
                 match field_name.as_str() {
                     "name" => edit_state.name.clone(),
-                    "taxes" => {
-                        let taxes_str = serde_json::to_string(&edit_state.taxes)
-                            .unwrap_or_else(|_| "[]".to_string());
-                        taxes_str
-                    },
                     _ => content.clone()
                     }
             }   else { content.clone() };
@@ -144,18 +239,6 @@ pub fn table_cell<'a>(
                 .width(Length::Fixed(width))
                 .into()
                 }
-                "taxes" => {
-                    text_input("", &current_value)
-                    .on_input(move |new_value| {
-                        match serde_json::from_str::<Vec<Tax>>(&new_value) {
-                            Ok(taxes) => Message::EditTaxes(tax_group_id, taxes),
-                            Err(_) => Message::EditField(tax_group_id, field_name.to_string(), new_value)
-                        }
-                    })
-                    .size(12)
-                    .width(Length::Fixed(width))
-                    .into()
-                }
                 _ => {
                     text(content)
                     .color(Color::BLACK)
@@ -186,6 +269,51 @@ pub fn table_cell<'a>(
     }
 }
 
+// The taxes of a group being edited, each in its own fields like the add form
+fn taxes_cell(tax_group_id: i64, width: f32, edit_state: &TaxGroupEditState) -> Element<'static, Message> {
+    let mut taxes = Column::new().spacing(2);
+    for (index, fields) in edit_state.taxes.iter().enumerate() {
+        let field = move |placeholder: &str, value: &str, field_name: &'static str, width: f32| {
+            text_input(placeholder, value)
+                .on_input(move |new_value| Message::EditTax(tax_group_id, index, field_name.to_string(), new_value))
+                .size(12)
+                .width(Length::Fixed(width))
+        };
+
+        taxes = taxes.push(row![
+            field("ID", &fields.id, "id", 40_f32),
+            field("Name", &fields.name, "name", 90_f32),
+            field("Rate", &fields.rate, "rate", 60_f32),
+            field("Order", &fields.order, "order", 45_f32),
+            pick_list(TaxBase::ALL, Some(fields.base), move |base| Message::TaxBaseChanged(tax_group_id, index, base)).text_size(12).width(Length::Fixed(130_f32)),
+            button(text("X").size(12)).on_press(Message::RemoveTax(tax_group_id, index)).padding(2),
+        ].spacing(2).align_y(iced::Alignment::Center));
+    }
+    taxes = taxes.push(button(text("Add Tax").size(12)).on_press(Message::AddTax(tax_group_id)).padding(2));
+
+    if let Some(error) = &edit_state.error {
+        taxes = taxes.push(text(error.clone()).color(Color::from_rgb(0.8, 0.1, 0.1)).size(10));
+    }
+
+    Container::new(taxes.width(Length::Fixed(width)))
+        .padding(5)
+        .style(pos_table_row)
+        .into()
+}
+
+fn rounding_cell(tax_group_id: i64, width: f32, edit_state: &TaxGroupEditState) -> Element<'static, Message> {
+    let picker = pick_list(GroupRounding::options(), Some(GroupRounding::from(edit_state.rounding)), move |rounding| {
+        Message::RoundingChanged(tax_group_id, rounding)
+    })
+    .text_size(12)
+    .width(Length::Fixed(width));
+
+    Container::new(picker)
+        .padding(5)
+        .style(pos_table_row)
+        .into()
+}
+
 // Helper function for cells with actions
 pub fn table_cell_with_action(tax_group_id: i64, width: f32, is_editing: bool) -> Element<'static, Message> {
     let edit_save_button = if is_editing {
@@ -222,28 +350,56 @@ pub fn table_cell_with_action(tax_group_id: i64, width: f32, is_editing: bool) -
 
 
 fn taxes_to_string(taxes: Vec<Tax>) -> String {
-    
-    let tax_str = taxes.iter().enumerate().map(|(i, tax)|
-        if i == taxes.len() -1 {
-            tax.name.clone()
-        } else {
-            format!("{},", tax.name.clone())
-        }
-        
-    ).collect();
+    let tax_group = TaxGroup::new(0, String::new(), taxes);
 
-    tax_str
+    // Show taxes in charging order, marking the ones charged on earlier taxes
+    tax_group.ordered_taxes().iter().map(|tax| match tax.base {
+        TaxBase::Price => tax.name.clone(),
+        TaxBase::PriceAndEarlierTaxes => format!("{} (compound)", tax.name),
+    }).collect::<Vec<_>>().join(", ")
 }
 
-fn create_tax_buttons(taxes: Option<Vec<Tax>>) -> Element<'static, Message> {
-    let mut column = Column::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if let Some(taxes) = taxes {
-        for tax in taxes {
-            column = column.push(button(text(tax.name.clone())).on_press(Message::TaxPressed(tax.id)));
-        }
-    };
+    fn tax(id: i64, rate: &str, order: i32, base: TaxBase) -> Tax {
+        let mut tax = Tax::new(id, format!("Tax {}", id), rate.parse().unwrap());
+        tax.order = order;
+        tax.base = base;
+        tax
+    }
 
-    column.into()
+    fn included(tax_group: &TaxGroup, gross: i64) -> (Money, Money) {
+        let taxes = tax_group.calculate_included_taxes(Money::from_cents(gross), RoundingMode::HalfUp);
+        let tax: Money = taxes.iter().map(|t| t.amount).sum();
+        (Money::from_cents(gross) - tax, tax)
+    }
+
+    #[test]
+    fn backs_tax_out_of_an_inclusive_price() {
+        let sales = TaxGroup::new(1, "Sales".to_string(), vec![tax(1, "10", 0, TaxBase::Price)]);
+        assert_eq!(included(&sales, 1_000), (Money::from_cents(909), Money::from_cents(91)));
+        assert_eq!(included(&sales, 1_100), (Money::from_cents(1_000), Money::from_cents(100)));
+        assert_eq!(included(&sales, -1_100), (Money::from_cents(-1_000), Money::from_cents(-100)));
+    }
+
+    #[test]
+    fn backs_compound_taxes_out_to_the_largest_net() {
+        let quebec = TaxGroup::new(1, "Quebec".to_string(), vec![
+            tax(1, "5", 0, TaxBase::Price),
+            tax(2, "9.975", 1, TaxBase::PriceAndEarlierTaxes),
+        ]);
 
-}
\ No newline at end of file
+        for gross in [1, 99, 1_000, 2_299, 12_345] {
+            let taxes = quebec.calculate_included_taxes(Money::from_cents(gross), RoundingMode::HalfUp);
+            let net = taxes[0].taxable;
+            let tax: Money = taxes.iter().map(|t| t.amount).sum();
+
+            // Adding the taxes back never goes over the price, and a cent more of net would
+            assert!(net + tax <= Money::from_cents(gross));
+            let more = net + Money::from_cents(1);
+            assert!(more + quebec.calculate_taxes(more, RoundingMode::HalfUp).iter().map(|t| t.amount).sum::<Money>() > Money::from_cents(gross));
+        }
+    }
+}
@@ -2,6 +2,7 @@ use iced::advanced::graphics::text::cosmic_text::ttf_parser::name;
 use iced::widget::{button, column, row, text, text_input, Button, Column, Container, Row};
 use iced::{Alignment, Color, Element, Length, Task};
 
+use crate::core::money::Rate;
 use crate::core::tax::Tax;
 use crate::ui::custom_appearances::{pos_table_header, pos_table_row, multiple_items};
 
@@ -34,8 +35,8 @@ impl TestView {
 
 
         //default Taxes
-        let tax = Tax::new(1_i64, "Sales".to_string(), 7.75_f64, );
-        let tax2 = Tax::new(2_i64, "Liquor".to_string(), 8.75_f64, );
+        let tax = Tax::new(1_i64, "Sales".to_string(), Rate::from_basis_points(775), );
+        let tax2 = Tax::new(2_i64, "Liquor".to_string(), Rate::from_basis_points(875), );
         
         //push to tax vec
         taxvec.push(tax);
@@ -154,11 +155,11 @@ impl TaxGroup {
         }
     }
     
-    pub fn get_tax_percent(&self, tax_name: &str) -> Option<f64> {
+    pub fn get_tax_percent(&self, tax_name: &str) -> Option<Rate> {
         // Search for the tax by name and return its percent if found
         for tax in &self.taxes {
             if tax.name == tax_name {
-                return Some(tax.percent);
+                return Some(tax.rate);
             }
         }
         None
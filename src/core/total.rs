@@ -1,7 +0,0 @@
-pub struct Total {
-    pub subtotal: f64,
-    pub total: f64,
-    pub tax: f64,
-    pub service_charge: f64,
-    pub gratuity: f64,
-}
\ No newline at end of file
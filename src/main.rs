@@ -1,4 +1,4 @@
-use iced::widget::{button, column, horizontal_rule, horizontal_space, row, text, text_input, vertical_rule, Container};
+use iced::widget::{button, column, horizontal_rule, horizontal_space, pick_list, row, text, text_input, vertical_rule, Container};
 use iced::{Alignment, Element, Subscription, Task, Theme, Vector};
 use iced_core::alignment::Vertical;
 mod core;
@@ -19,6 +19,11 @@ use ui::{
     //service charges
     service_charge_list_view,
     add_service_charge_view,
+    //discounts
+    discount_list_view,
+    add_discount_view,
+    //receipts
+    receipt_view,
 };
 use ui::{
     //items
@@ -36,7 +41,13 @@ use ui::{
     //service charges
     service_charge_list_view::ServiceChargeView,
     add_service_charge_view::AddServiceChargeForm,
+    //discounts
+    discount_list_view::DiscountView,
+    add_discount_view::AddDiscountForm,
+    //receipts
+    receipt_view::{Catalog, ReceiptView},
 };
+use core::currency::{Currency, Locale, MoneyFormat};
 use std::collections::BTreeMap;
 use std::rc::Rc;
 use std::cell::RefCell;
@@ -63,10 +74,17 @@ pub struct AppState {
     //service charges
     service_charge_view: ServiceChargeView,
     add_service_charge_view: AddServiceChargeForm,
+    //discounts
+    discount_view: DiscountView,
+    add_discount_view: AddDiscountForm,
+    //receipts
+    receipt_view: ReceiptView,
 
     //test ui
     test_ui: core::testing_ui_stuff::TestView,
 
+    //how prices in the catalog are written
+    money_format: MoneyFormat,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -101,6 +119,8 @@ pub enum View {
     Taxes,
     Gratuities,
     ServiceCharges,
+    Discounts,
+    Receipt,
 }
 
 struct RC {
@@ -133,6 +153,13 @@ enum Message {
     ServiceChargeList(service_charge_list_view::Message),
     AddServiceCharge(add_service_charge_view::Message),
 
+    //discounts
+    DiscountList(discount_list_view::Message),
+    AddDiscount(add_discount_view::Message),
+
+    //receipts
+    Receipt(receipt_view::Message),
+
     //testing multi Window application
     MainWindowOpened(window::Id),
     OpenWindow(WindowType, String),
@@ -143,6 +170,10 @@ enum Message {
     //ui test
     TestingUIStuff(core::testing_ui_stuff::Message),
 
+    //catalog currency
+    CurrencyChanged(Currency),
+    LocaleChanged(Locale),
+
     //moving back to views inside the main window
     SetView(View),
     WindowResized((window::Id, iced::Size)),
@@ -183,10 +214,16 @@ impl RC {
             //service charges
             service_charge_view: ServiceChargeView::new(),
             add_service_charge_view: AddServiceChargeForm::new(),
+            //discounts
+            discount_view: DiscountView::new(),
+            add_discount_view: AddDiscountForm::new(),
+            //receipts
+            receipt_view: ReceiptView::new(),
 
             //test ui
             test_ui: core::testing_ui_stuff::TestView::new(),
 
+            money_format: MoneyFormat::default(),
         }));
 
         let mut windows = BTreeMap::new();
@@ -209,7 +246,8 @@ impl RC {
         match message {
             Message::ItemList(ui_message) => {
                 let mut app_state = state.shared_state.borrow_mut();
-                ItemView::update(&mut app_state.item_view, ui_message).map(Message::ItemList)
+                let money_format = app_state.money_format;
+                ItemView::update(&mut app_state.item_view, ui_message, money_format).map(Message::ItemList)
             }
             Message::AddItem(msg) => {
                 let mut app_state = state.shared_state.borrow_mut();
@@ -285,7 +323,8 @@ impl RC {
             }
             Message::ServiceChargeList(ui_message) => {
                 let mut app_state = state.shared_state.borrow_mut();
-                ServiceChargeView::update(&mut app_state.service_charge_view, ui_message).map(Message::ServiceChargeList)
+                let money_format = app_state.money_format;
+                ServiceChargeView::update(&mut app_state.service_charge_view, ui_message, money_format).map(Message::ServiceChargeList)
             }
             Message::AddServiceCharge(msg) => {
                 let mut app_state = state.shared_state.borrow_mut();
@@ -302,6 +341,46 @@ impl RC {
                     Task::none()
                 }
             }
+            Message::DiscountList(ui_message) => {
+                let mut app_state = state.shared_state.borrow_mut();
+                let money_format = app_state.money_format;
+                DiscountView::update(&mut app_state.discount_view, ui_message, money_format).map(Message::DiscountList)
+            }
+            Message::AddDiscount(msg) => {
+                let mut app_state = state.shared_state.borrow_mut();
+
+                if let Some(action) = add_discount_view::AddDiscountForm::update( &mut app_state.add_discount_view, msg ) {
+                    match action {
+                        add_discount_view::Action::AddNewDiscount(discount) => {
+                            Task::perform(async move {
+                                discount_list_view::Message::NewDiscount(discount)
+                            }, Message::DiscountList)
+                        }
+                    }
+                } else {
+                    Task::none()
+                }
+            }
+            Message::Receipt(receipt_view::Message::CreateTax(rate)) => {
+                let mut app_state = state.shared_state.borrow_mut();
+                app_state.add_tax_view = AddTaxForm::with_rate(rate);
+                state.view = View::Taxes;
+
+                Task::none()
+            }
+            Message::Receipt(ui_message) => {
+                let mut app_state = state.shared_state.borrow_mut();
+                let app_state = &mut *app_state;
+                let catalog = Catalog {
+                    items: app_state.item_view.items(),
+                    service_charges: app_state.service_charge_view.service_charges(),
+                    gratuities: app_state.gratuity_view.gratuities(),
+                    discounts: app_state.discount_view.discounts(),
+                    taxes: app_state.tax_view.taxes(),
+                    format: app_state.money_format,
+                };
+                ReceiptView::update(&mut app_state.receipt_view, ui_message, &catalog).map(Message::Receipt)
+            }
             Message::TestingUIStuff(ui_message) => {
                 let mut app_state = state.shared_state.borrow_mut();
 
@@ -388,9 +467,24 @@ impl RC {
 
                 Task::none()
             }
+            Message::CurrencyChanged(currency) => {
+                let mut app_state = state.shared_state.borrow_mut();
+                app_state.money_format.currency = currency;
+
+                Task::none()
+            }
+            Message::LocaleChanged(locale) => {
+                let mut app_state = state.shared_state.borrow_mut();
+                app_state.money_format.locale = locale;
+
+                Task::none()
+            }
             Message::SetView(view) => {
                 state.view = view;
-                Task::none()
+                match view {
+                    View::Receipt => Task::done(Message::Receipt(receipt_view::Message::Refresh)),
+                    _ => Task::none(),
+                }
             }
             Message::WindowResized((_id, size)) => {
                 state.window_size = (size.width, size.height);
@@ -407,6 +501,10 @@ impl RC {
 
     fn view(state: &Self, window_id: window::Id) -> Element<Message> {
 
+        let money_format = state.shared_state.try_borrow()
+            .map(|app_state| app_state.money_format)
+            .unwrap_or_default();
+
         if let Some(window) = state.windows.get(&window_id) {
 
             match window.window_type {
@@ -436,7 +534,7 @@ impl RC {
                                 row![
                                     AddItemForm::view(&add_item_view).map(Message::AddItem),
                                     iced::widget::vertical_rule(1),        
-                                    ItemView::view(&item_view).map(Message::ItemList),
+                                    ItemView::view(&item_view, money_format).map(Message::ItemList),
                                 ]
                             ).into()
                         }
@@ -499,7 +597,7 @@ impl RC {
                                 row![
                                     AddGratuityForm::view(&add_gratuity).map(Message::AddGratuity),
                                     iced::widget::vertical_rule(1), 
-                                    GratuityView::view(&gratuity_view).map(Message::GratuityList),
+                                    GratuityView::view(&gratuity_view, money_format).map(Message::GratuityList),
                                 ]
                             ).into()
                         }
@@ -520,10 +618,48 @@ impl RC {
                                 row![
                                     AddServiceChargeForm::view(&add_service_charge).map(Message::AddServiceCharge),
                                     iced::widget::vertical_rule(1), 
-                                    ServiceChargeView::view(&service_charge_view).map(Message::ServiceChargeList),
+                                    ServiceChargeView::view(&service_charge_view, money_format).map(Message::ServiceChargeList),
+                                ]
+                            ).into()
+                        }
+                        View::Discounts => {
+                            let discount_view = if let Ok(app_state) = state.shared_state.try_borrow() {
+                                app_state.discount_view.clone()
+                            } else {
+                                DiscountView::new()
+                            };
+
+                            let add_discount = if let Ok(app_state) = state.shared_state.try_borrow() {
+                                app_state.add_discount_view.clone()
+                            } else {
+                                AddDiscountForm::new()
+                            };
+        
+                            Container::new(
+                                row![
+                                    AddDiscountForm::view(&add_discount).map(Message::AddDiscount),
+                                    iced::widget::vertical_rule(1), 
+                                    DiscountView::view(&discount_view, money_format).map(Message::DiscountList),
                                 ]
                             ).into()
                         }
+                        View::Receipt => {
+                            if let Ok(app_state) = state.shared_state.try_borrow() {
+                                let catalog = Catalog {
+                                    items: app_state.item_view.items(),
+                                    service_charges: app_state.service_charge_view.service_charges(),
+                                    gratuities: app_state.gratuity_view.gratuities(),
+                                    discounts: app_state.discount_view.discounts(),
+                                    taxes: app_state.tax_view.taxes(),
+                                    format: money_format,
+                                };
+                                Container::new(
+                                    ReceiptView::view(&app_state.receipt_view, &catalog).map(Message::Receipt)
+                                ).into()
+                            } else {
+                                horizontal_space().into()
+                            }
+                        }
                     };
 
 
@@ -535,6 +671,12 @@ impl RC {
                             button(text("Taxes")).on_press(Message::SetView(View::Taxes)).width(iced::Length::Fill).style(button::primary),
                             button(text("Gratuities")).on_press(Message::SetView(View::Gratuities)).width(iced::Length::Fill).style(button::primary), 
                             button(text("Service Charges")).on_press(Message::SetView(View::ServiceCharges)).width(iced::Length::Fill).style(button::primary),  
+                            button(text("Discounts")).on_press(Message::SetView(View::Discounts)).width(iced::Length::Fill).style(button::primary),
+                            button(text("Receipt")).on_press(Message::SetView(View::Receipt)).width(iced::Length::Fill).style(button::primary),
+
+                            text("Currency"),
+                            pick_list(Currency::ALL, Some(money_format.currency), Message::CurrencyChanged).width(iced::Length::Fill),
+                            pick_list(Locale::ALL, Some(money_format.locale), Message::LocaleChanged).width(iced::Length::Fill),
 
                             text(format!("X: {}", state.window_size.0)),
                             text(format!("Y: {}", state.window_size.1)),
@@ -577,7 +719,7 @@ impl RC {
                         row![
                             AddItemForm::view(&add_item_view).map(Message::AddItem),
                             iced::widget::vertical_rule(1),        
-                            ItemView::view(&item_view).map(Message::ItemList),
+                            ItemView::view(&item_view, money_format).map(Message::ItemList),
                         ]
                     ).into()
                 }
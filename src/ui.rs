@@ -19,4 +19,11 @@ pub mod add_gratuity_view;
 
 //service charge views
 pub mod service_charge_list_view;
-pub mod add_service_charge_view;
\ No newline at end of file
+pub mod add_service_charge_view;
+
+//discount views
+pub mod discount_list_view;
+pub mod add_discount_view;
+
+//receipt views
+pub mod receipt_view;
@@ -0,0 +1,270 @@
+use std::fmt;
+
+use iced::{Element, Length};
+use iced::widget::{button, column, pick_list, row, text, text_input, Container};
+
+use crate::core::{
+    discount::{Discount, DiscountTarget, DiscountTiming, DiscountValue},
+    money::{Money, Rate},
+    calculations::{
+        validate_money,
+        convert_to_money,
+        validate_percent,
+        convert_percent_to_rate,
+        validate_i64,
+        convert_to_i64
+    },
+};
+
+use crate::ui::custom_appearances;
+use custom_appearances::validator;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AmountKind {
+    #[default]
+    Percent,
+    Fixed,
+}
+
+impl AmountKind {
+    const ALL: [AmountKind; 2] = [AmountKind::Percent, AmountKind::Fixed];
+}
+
+impl fmt::Display for AmountKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountKind::Percent => write!(f, "Percent"),
+            AmountKind::Fixed => write!(f, "Fixed amount"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TargetKind {
+    Line,
+    Category,
+    #[default]
+    Receipt,
+}
+
+impl TargetKind {
+    const ALL: [TargetKind; 3] = [TargetKind::Line, TargetKind::Category, TargetKind::Receipt];
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetKind::Line => write!(f, "Line"),
+            TargetKind::Category => write!(f, "Category"),
+            TargetKind::Receipt => write!(f, "Whole receipt"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AddDiscountForm {
+    discount_id: validator::Input,
+    discount_name: String,
+    amount_kind: AmountKind,
+    amount: validator::Input,
+    target_kind: TargetKind,
+    // Line number or category name, depending on the target
+    target: String,
+    timing: DiscountTiming,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Submit,
+
+    DiscountIdChanged(validator::Message),
+    DiscountNameChanged(String),
+    AmountKindChanged(AmountKind),
+    AmountChanged(validator::Message),
+    TargetKindChanged(TargetKind),
+    TargetChanged(String),
+    TimingChanged(DiscountTiming),
+}
+
+pub enum Action {
+    AddNewDiscount(Discount)
+}
+
+
+impl AddDiscountForm {
+    pub fn new() -> Self {
+        AddDiscountForm {
+            discount_id: validator::Input::default(),
+            discount_name: String::new(),
+            amount_kind: AmountKind::default(),
+            amount: validator::Input::default(),
+            target_kind: TargetKind::default(),
+            target: String::new(),
+            timing: DiscountTiming::default(),
+        }
+    }
+
+    fn validate_amount(&self) -> fn(&str) -> bool {
+        match self.amount_kind {
+            AmountKind::Percent => validate_percent,
+            AmountKind::Fixed => validate_money,
+        }
+    }
+
+    pub fn update(state: &mut Self, message: Message) -> Option<Action> {
+        match message {
+            Message::Submit => {
+                let discount_id: i64 = if validate_i64(&state.discount_id.value)
+                {
+                    convert_to_i64(&state.discount_id.value)
+                } else { 0 };
+
+                let value = match state.amount_kind {
+                    AmountKind::Percent => DiscountValue::Percent(if validate_percent(&state.amount.value)
+                    {
+                        convert_percent_to_rate(&state.amount.value)
+                    } else { Rate::ZERO }),
+                    AmountKind::Fixed => DiscountValue::Fixed(if validate_money(&state.amount.value)
+                    {
+                        convert_to_money(&state.amount.value)
+                    } else { Money::ZERO }),
+                };
+
+                // Lines are numbered from 1 on screen
+                let target = match state.target_kind {
+                    TargetKind::Line => DiscountTarget::Line(state.target.trim().parse::<usize>().unwrap_or(1).saturating_sub(1)),
+                    TargetKind::Category => DiscountTarget::Category(state.target.trim().to_string()),
+                    TargetKind::Receipt => DiscountTarget::Receipt,
+                };
+
+                let new_discount = Discount::new(discount_id, state.discount_name.to_string(), value, target, state.timing);
+
+                Some(Action::AddNewDiscount(new_discount))
+            }
+            Message::DiscountIdChanged(id) => {
+                match id {
+                    validator::Message::RawInput(input) => {
+                        state.discount_id.value = input;
+                        state.discount_id.is_valid =
+                            validator::validate(&state.discount_id.value, validate_i64);
+
+                        if !state.discount_id.is_valid {
+                            state.discount_id.value = String::new();
+                            state.discount_id.is_valid = true;
+                            state.discount_id.placeholder = "Numbers Only".to_string();
+                        } else { state.discount_id.placeholder = "".to_string() }
+                    }
+                    validator::Message::RawSubmit(input) => {
+                        state.discount_id.value = input;
+                        state.discount_id.is_valid =
+                            validator::validate(&state.discount_id.value, validate_i64);
+                    }
+                }
+
+                None
+            }
+            Message::DiscountNameChanged(name) => {
+                state.discount_name = name;
+
+                None
+            }
+            Message::AmountKindChanged(amount_kind) => {
+                state.amount_kind = amount_kind;
+                state.amount.is_valid = state.amount.value.is_empty()
+                    || validator::validate(&state.amount.value, state.validate_amount());
+
+                None
+            }
+            Message::AmountChanged(amount) => {
+                let validate_amount = state.validate_amount();
+                match amount {
+                    validator::Message::RawInput(input) => {
+                        state.amount.value = input;
+                        state.amount.is_valid =
+                            validator::validate(&state.amount.value, validate_amount);
+
+                        if !state.amount.is_valid {
+                            state.amount.value = String::new();
+                            state.amount.is_valid = true;
+                            state.amount.placeholder = "Numbers Only".to_string();
+                        } else { state.amount.placeholder = "".to_string() }
+                    }
+                    validator::Message::RawSubmit(input) => {
+                        state.amount.value = input;
+                        state.amount.is_valid =
+                            validator::validate(&state.amount.value, validate_amount);
+                    }
+                }
+
+                None
+            }
+            Message::TargetKindChanged(target_kind) => {
+                state.target_kind = target_kind;
+
+                None
+            }
+            Message::TargetChanged(target) => {
+                state.target = target;
+
+                None
+            }
+            Message::TimingChanged(timing) => {
+                state.timing = timing;
+
+                None
+            }
+        }
+    }
+
+
+    pub fn view(state: &Self) -> Element<'static, Message>{
+        let target_label = match state.target_kind {
+            TargetKind::Line => "Line Number",
+            TargetKind::Category => "Category",
+            TargetKind::Receipt => "",
+        };
+
+        let mut target_column = column![
+            text("Applies To").size(18),
+            pick_list(TargetKind::ALL, Some(state.target_kind), Message::TargetKindChanged).width(120),
+        ];
+        if state.target_kind != TargetKind::Receipt {
+            target_column = target_column.push(text(target_label).size(14));
+            target_column = target_column.push(text_input("", &state.target).on_input(Message::TargetChanged).width(120));
+        }
+
+        Container::new(
+            column![
+                row![
+                    text("Add Discounts").size(16),
+                ].padding(8),
+                iced::widget::horizontal_rule(1),
+                column![
+                    text("ID").size(18),
+                    validator::view(&state.discount_id.value.clone(), &state.discount_id.placeholder, state.discount_id.is_valid).map(Message::DiscountIdChanged),
+                ].padding(8),
+                column![
+                    text("Name").size(18),
+                    text_input("", &state.discount_name).on_input(Message::DiscountNameChanged).id("1".to_string()).width(120)
+                    ].padding(8),
+                column![
+                    text("Amount").size(18),
+                    pick_list(AmountKind::ALL, Some(state.amount_kind), Message::AmountKindChanged).width(120),
+                    validator::view(&state.amount.value, &state.amount.placeholder, state.amount.is_valid).map(Message::AmountChanged),
+                ].padding(8),
+                target_column.padding(8),
+                column![
+                    text("Timing").size(18),
+                    pick_list(DiscountTiming::ALL, Some(state.timing), Message::TimingChanged).width(120)
+                    ].padding(8),
+                row![
+                    iced::widget::horizontal_space().width(Length::Fill),
+                    button("Add Discount").on_press(Message::Submit).width(Length::Shrink),
+                    iced::widget::horizontal_space().width(Length::Fill),
+                ].padding(8).width(130),
+            ]
+        )
+        .width(130)
+        .into()
+    }
+}
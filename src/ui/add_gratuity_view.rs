@@ -1,15 +1,26 @@
+use std::fmt;
+
 use iced::alignment::Horizontal;
 use iced::Alignment::Center;
 use iced::{Alignment, Element, Length};
-use iced::widget::{button, checkbox, column, container, row, text, text_input, Container};
+use iced::widget::{button, checkbox, column, container, pick_list, row, text, text_input, Container};
 
 use crate::core::{
-    gratuity::Gratuity,
+    charge::ChargeBase,
+    money::{Money, Rate},
+    gratuity::{Gratuity, GratuityCondition, GratuityRule, RuleBase},
     tax::Tax,
     tax_group::TaxGroup,
+    exemption::ExemptTaxes,
     calculations::{
-        validate_f64,
-        convert_to_f64,
+        validate_money,
+        convert_to_money,
+        validate_u32,
+        convert_to_u32,
+        validate_percent,
+        convert_percent_to_rate,
+        validate_i32,
+        convert_to_i32,
         validate_i64,
         convert_to_i64
     },
@@ -18,13 +29,44 @@ use crate::core::{
 use crate::ui::custom_appearances;
 use custom_appearances::validator;
 
+// When the gratuity is charged
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Activation {
+    #[default]
+    Always,
+    PartySize,
+    Subtotal,
+}
+
+impl Activation {
+    const ALL: [Activation; 3] = [Activation::Always, Activation::PartySize, Activation::Subtotal];
+}
+
+impl fmt::Display for Activation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Activation::Always => write!(f, "Always"),
+            Activation::PartySize => write!(f, "Party size"),
+            Activation::Subtotal => write!(f, "Subtotal over"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AddGratuityForm {
     gratuity_id: validator::Input,
     gratuity_name: String,
     percent: validator::Input,
+    base: ChargeBase,
+    order: validator::Input,
     tax_group: String,
-    tax_overide: bool,
+    // Exempt from every tax in the group, otherwise just from the listed tax ids
+    tax_exempt: bool,
+    exempt_tax_ids: String,
+    activation: Activation,
+    // Guest count or subtotal, depending on the activation
+    threshold: validator::Input,
+    rule_base: RuleBase,
 }
 
 #[derive(Debug, Clone)]
@@ -34,8 +76,14 @@ pub enum Message {
     GratuityIdChanged(validator::Message),
     GratuityNameChanged(String),
     PercentChanged(validator::Message),
+    BaseChanged(ChargeBase),
+    OrderChanged(validator::Message),
     TaxGroupChanged(String),
-    TaxOverideChanged(bool),
+    TaxExemptChanged(bool),
+    ExemptTaxIdsChanged(String),
+    ActivationChanged(Activation),
+    ThresholdChanged(validator::Message),
+    RuleBaseChanged(RuleBase),
 }
 
 pub enum Action {
@@ -49,8 +97,21 @@ impl AddGratuityForm {
             gratuity_id: validator::Input::default(),
             gratuity_name: String::new(),
             percent: validator::Input::default(),
+            base: ChargeBase::default(),
+            order: validator::Input::default(),
             tax_group: String::new(),
-            tax_overide: false,
+            tax_exempt: false,
+            exempt_tax_ids: String::new(),
+            activation: Activation::default(),
+            threshold: validator::Input::default(),
+            rule_base: RuleBase::default(),
+        }
+    }
+
+    fn validate_threshold(&self) -> fn(&str) -> bool {
+        match self.activation {
+            Activation::PartySize => validate_u32,
+            Activation::Always | Activation::Subtotal => validate_money,
         }
     }
 
@@ -62,13 +123,24 @@ impl AddGratuityForm {
                     convert_to_i64(&state.gratuity_id.value)
                 } else { 0 };
 
-                let percent: f64 = if validate_f64(&state.percent.value)
+                let percent: Rate = if validate_percent(&state.percent.value)
                 {
-                    convert_to_f64(&state.percent.value)
-                } else { 0.0 };
+                    convert_percent_to_rate(&state.percent.value)
+                } else { Rate::ZERO };
+
+                let order: i32 = if validate_i32(&state.order.value)
+                {
+                    convert_to_i32(&state.order.value)
+                } else { 0 };
 
                 let gratuity_name = &state.gratuity_name;
 
+                let exempt_taxes = if state.tax_exempt {
+                    ExemptTaxes::All
+                } else {
+                    state.exempt_tax_ids.parse::<ExemptTaxes>().unwrap_or_default()
+                };
+
                 //create sales tax to add to tax group
                 let sales_tax = Tax::default();
 
@@ -79,7 +151,22 @@ impl AddGratuityForm {
                 //create new taxgroup with the sales tax
                 let tax_group = TaxGroup::new(1, "Default".to_string(), taxes);
 
-                let new_gratuity = Gratuity::new(gratuity_id, gratuity_name.to_string(), percent, tax_group, state.tax_overide);
+                let mut new_gratuity = Gratuity::new(gratuity_id, gratuity_name.to_string(), percent, state.base, order, tax_group, exempt_taxes);
+
+                let condition = match state.activation {
+                    Activation::Always => None,
+                    Activation::PartySize => Some(GratuityCondition::PartySize(if validate_u32(&state.threshold.value)
+                    {
+                        convert_to_u32(&state.threshold.value)
+                    } else { 0 })),
+                    Activation::Subtotal => Some(GratuityCondition::Subtotal(if validate_money(&state.threshold.value)
+                    {
+                        convert_to_money(&state.threshold.value)
+                    } else { Money::ZERO })),
+                };
+                if let Some(condition) = condition {
+                    new_gratuity.rules.push(GratuityRule::new(condition, state.rule_base));
+                }
 
                 Some(Action::AddNewGratuity(new_gratuity))
             }
@@ -119,7 +206,7 @@ impl AddGratuityForm {
                     validator::Message::RawInput(input) => {
                         state.percent.value = input;
                         state.percent.is_valid =
-                            validator::validate(&state.percent.value, validate_f64);
+                            validator::validate(&state.percent.value, validate_percent);
                         
                         if !state.percent.is_valid { 
                             state.percent.value = String::new();
@@ -130,19 +217,85 @@ impl AddGratuityForm {
                     validator::Message::RawSubmit(input) => {
                         state.percent.value = input;
                         state.percent.is_valid =
-                            validator::validate(&state.percent.value, validate_f64);
+                            validator::validate(&state.percent.value, validate_percent);
                     }
                 }
             
                 None
             }
+            Message::BaseChanged(base) => {
+                state.base = base;
+
+                None
+            }
+            Message::OrderChanged(order) => {
+                match order {
+                    validator::Message::RawInput(input) => {
+                        state.order.value = input;
+                        state.order.is_valid =
+                            validator::validate(&state.order.value, validate_i32);
+
+                        if !state.order.is_valid { 
+                            state.order.value = String::new();
+                            state.order.is_valid = true;
+                            state.order.placeholder = "Numbers Only".to_string();
+                        } else { state.order.placeholder = "".to_string() }
+                    }
+                    validator::Message::RawSubmit(input) => {
+                        state.order.value = input;
+                        state.order.is_valid =
+                            validator::validate(&state.order.value, validate_i32);
+                    }
+                }
+
+                None
+            }
             Message::TaxGroupChanged(taxgroup) => {
                 state.tax_group = taxgroup;
 
                 None
             }
-            Message::TaxOverideChanged(taxoveride) => {
-                state.tax_overide = taxoveride;
+            Message::TaxExemptChanged(tax_exempt) => {
+                state.tax_exempt = tax_exempt;
+                None
+            }
+            Message::ExemptTaxIdsChanged(exempt_tax_ids) => {
+                state.exempt_tax_ids = exempt_tax_ids;
+                None
+            }
+            Message::ActivationChanged(activation) => {
+                state.activation = activation;
+                state.threshold.is_valid = state.threshold.value.is_empty()
+                    || validator::validate(&state.threshold.value, state.validate_threshold());
+
+                None
+            }
+            Message::ThresholdChanged(threshold) => {
+                let validate_threshold = state.validate_threshold();
+                match threshold {
+                    validator::Message::RawInput(input) => {
+                        state.threshold.value = input;
+                        state.threshold.is_valid =
+                            validator::validate(&state.threshold.value, validate_threshold);
+
+                        if !state.threshold.is_valid {
+                            state.threshold.value = String::new();
+                            state.threshold.is_valid = true;
+                            state.threshold.placeholder = "Numbers Only".to_string();
+                        } else { state.threshold.placeholder = "".to_string() }
+                    }
+                    validator::Message::RawSubmit(input) => {
+                        state.threshold.value = input;
+                        state.threshold.is_valid =
+                            validator::validate(&state.threshold.value, validate_threshold);
+                    }
+                }
+
+                None
+            }
+            Message::RuleBaseChanged(rule_base) => {
+                state.rule_base = rule_base;
+
                 None
             }
         }
@@ -150,6 +303,22 @@ impl AddGratuityForm {
 
 
     pub fn view<'a>(state: &Self) -> Element<'static, Message>{    
+        let threshold_label = match state.activation {
+            Activation::PartySize => "Guests Or More",
+            Activation::Always | Activation::Subtotal => "Amount",
+        };
+
+        // An automatic gratuity is charged on the subtotal its rule looks at
+        let mut activation_column = column![
+            text("Applies").size(18),
+            pick_list(Activation::ALL, Some(state.activation), Message::ActivationChanged).width(120),
+        ];
+        if state.activation != Activation::Always {
+            activation_column = activation_column.push(text(threshold_label).size(14));
+            activation_column = activation_column.push(validator::view(&state.threshold.value, &state.threshold.placeholder, state.threshold.is_valid).map(Message::ThresholdChanged));
+            activation_column = activation_column.push(pick_list(RuleBase::ALL, Some(state.rule_base), Message::RuleBaseChanged).width(120));
+        }
+
         Container::new(
             column![
                 row![
@@ -168,12 +337,23 @@ impl AddGratuityForm {
                     text("percent").size(18),
                     validator::view(&state.percent.value, &state.percent.placeholder, state.percent.is_valid).map(Message::PercentChanged),
                 ].padding(8),
+                column![
+                    text("Calculated On").size(18),
+                    pick_list(ChargeBase::ALL, Some(state.base), Message::BaseChanged).width(120)
+                    ].padding(8),
+                activation_column.padding(8),
+                column![
+                    text("Order").size(18),
+                    validator::view(&state.order.value, &state.order.placeholder, state.order.is_valid).map(Message::OrderChanged),
+                ].padding(8),
                 column![
                     text("Tax Group").size(18),
                     text_input("", &state.tax_group).on_input(Message::TaxGroupChanged).width(120)
                     ].padding(8),
                 column![
-                    checkbox("Tax Overide", state.tax_overide).on_toggle(Message::TaxOverideChanged).spacing(4)
+                    checkbox("Tax Exempt", state.tax_exempt).on_toggle(Message::TaxExemptChanged).spacing(4),
+                    text("Exempt Tax IDs").size(14),
+                    text_input("2, 3", &state.exempt_tax_ids).on_input(Message::ExemptTaxIdsChanged).width(120)
                     ].spacing(8).padding(8),
                 row![
                     iced::widget::horizontal_space().width(Length::Fill),
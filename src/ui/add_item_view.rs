@@ -1,15 +1,18 @@
 use iced::alignment::Horizontal;
 use iced::Alignment::Center;
 use iced::{Alignment, Element, Length};
-use iced::widget::{button, checkbox, column, container, row, text, text_input, Container};
+use iced::widget::{button, checkbox, column, container, pick_list, row, text, text_input, Container};
 
 use crate::core::{
+    money::Money,
     items::Item,
+    quantity::Unit,
     tax::Tax,
     tax_group::TaxGroup,
+    exemption::ExemptTaxes,
     calculations::{
-        validate_f64,
-        convert_to_f64,
+        validate_money,
+        convert_to_money,
         validate_i64,
         convert_to_i64
     },
@@ -24,8 +27,12 @@ pub struct AddItemForm {
     item_name: String,
     category: String,
     price: validator::Input,
+    unit: Unit,
     tax_group: String,
-    tax_overide: bool,
+    // Exempt from every tax in the group, otherwise just from the listed tax ids
+    tax_exempt: bool,
+    exempt_tax_ids: String,
+    tax_inclusive: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -36,8 +43,11 @@ pub enum Message {
     ItemNameChanged(String),
     CategoryChanged(String),
     PriceChanged(validator::Message),
+    UnitChanged(Unit),
     TaxGroupChanged(String),
-    TaxOverideChanged(bool),
+    TaxExemptChanged(bool),
+    ExemptTaxIdsChanged(String),
+    TaxInclusiveChanged(bool),
 }
 
 pub enum Action {
@@ -52,8 +62,11 @@ impl AddItemForm {
             item_name: String::new(),
             category: String::new(),
             price: validator::Input::default(),
+            unit: Unit::default(),
             tax_group: String::new(),
-            tax_overide: false,
+            tax_exempt: false,
+            exempt_tax_ids: String::new(),
+            tax_inclusive: false,
         }
     }
 
@@ -65,15 +78,21 @@ impl AddItemForm {
                     convert_to_i64(&state.item_id.value)
                 } else { 0 };
 
-                let price: f64 = if validate_f64(&state.price.value)
+                let price: Money = if validate_money(&state.price.value)
                 {
-                    convert_to_f64(&state.price.value)
-                } else { 0.0 };
+                    convert_to_money(&state.price.value)
+                } else { Money::ZERO };
 
                 let item_name = &state.item_name;
 
                 let item_category = &state.category;
 
+                let exempt_taxes = if state.tax_exempt {
+                    ExemptTaxes::All
+                } else {
+                    state.exempt_tax_ids.parse::<ExemptTaxes>().unwrap_or_default()
+                };
+
                 //create sales tax to add to tax group
                 let sales_tax = Tax::default();
 
@@ -85,7 +104,10 @@ impl AddItemForm {
                 let tax_group = TaxGroup::new(1, "Default".to_string(), taxes);
 
 
-                let new_item = Item::new(item_id, item_name.to_string(), item_category.to_string(), price, tax_group, state.tax_overide);
+                let new_item = Item::new(item_id, item_name.to_string(), item_category.to_string(), price, tax_group)
+                    .with_unit(state.unit)
+                    .with_exempt_taxes(exempt_taxes)
+                    .with_tax_inclusive(state.tax_inclusive);
 
                 Some(Action::AddNewItem(new_item))
             }
@@ -131,7 +153,7 @@ impl AddItemForm {
                     validator::Message::RawInput(input) => {
                         state.price.value = input;
                         state.price.is_valid =
-                            validator::validate(&state.price.value, validate_f64);
+                            validator::validate(&state.price.value, validate_money);
                         
                         if !state.price.is_valid { 
                             state.price.value = String::new();
@@ -142,19 +164,32 @@ impl AddItemForm {
                     validator::Message::RawSubmit(input) => {
                         state.price.value = input;
                         state.price.is_valid =
-                            validator::validate(&state.price.value, validate_f64);
+                            validator::validate(&state.price.value, validate_money);
                     }
                 }
             
                 None
             }
+            Message::UnitChanged(unit) => {
+                state.unit = unit;
+
+                None
+            }
             Message::TaxGroupChanged(taxgroup) => {
                 state.tax_group = taxgroup;
 
                 None
             }
-            Message::TaxOverideChanged(taxoveride) => {
-                state.tax_overide = taxoveride;
+            Message::TaxExemptChanged(tax_exempt) => {
+                state.tax_exempt = tax_exempt;
+                None
+            }
+            Message::ExemptTaxIdsChanged(exempt_tax_ids) => {
+                state.exempt_tax_ids = exempt_tax_ids;
+                None
+            }
+            Message::TaxInclusiveChanged(tax_inclusive) => {
+                state.tax_inclusive = tax_inclusive;
                 None
             }
         }
@@ -184,12 +219,19 @@ impl AddItemForm {
                     text("Price").size(18),
                     validator::view(&state.price.value, &state.price.placeholder, state.price.is_valid).map(Message::PriceChanged),
                 ].padding(8),
+                column![
+                    text("Per").size(18),
+                    pick_list(Unit::ALL, Some(state.unit), Message::UnitChanged).width(120)
+                    ].padding(8),
                 column![
                     text("Tax Group").size(18),
                     text_input("", &state.tax_group).on_input(Message::TaxGroupChanged).width(120)
                     ].padding(8),
                 column![
-                    checkbox("Tax Overide", state.tax_overide).on_toggle(Message::TaxOverideChanged).spacing(4)
+                    checkbox("Tax Exempt", state.tax_exempt).on_toggle(Message::TaxExemptChanged).spacing(4),
+                    text("Exempt Tax IDs").size(14),
+                    text_input("2, 3", &state.exempt_tax_ids).on_input(Message::ExemptTaxIdsChanged).width(120),
+                    checkbox("Tax Inclusive", state.tax_inclusive).on_toggle(Message::TaxInclusiveChanged).spacing(4)
                     ].spacing(8).padding(8),
                 row![
                     iced::widget::horizontal_space().width(Length::Fill),
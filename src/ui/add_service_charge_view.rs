@@ -1,15 +1,24 @@
+use std::fmt;
+
 use iced::alignment::Horizontal;
 use iced::Alignment::Center;
 use iced::{Alignment, Element, Length};
-use iced::widget::{button, checkbox, column, container, row, text, text_input, Container};
+use iced::widget::{button, checkbox, column, container, pick_list, row, text, text_input, Container};
 
 use crate::core::{
+    charge::{ChargeBase, ChargeValue},
+    money::{Money, Rate},
     service_charge::ServiceCharge,
     tax::Tax,
     tax_group::TaxGroup,
+    exemption::ExemptTaxes,
     calculations::{
-        validate_f64,
-        convert_to_f64,
+        validate_money,
+        convert_to_money,
+        validate_percent,
+        convert_percent_to_rate,
+        validate_i32,
+        convert_to_i32,
         validate_i64,
         convert_to_i64
     },
@@ -18,13 +27,40 @@ use crate::core::{
 use crate::ui::custom_appearances;
 use custom_appearances::validator;
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChargeType {
+    #[default]
+    Percent,
+    Fixed,
+    PerGuest,
+}
+
+impl ChargeType {
+    const ALL: [ChargeType; 3] = [ChargeType::Percent, ChargeType::Fixed, ChargeType::PerGuest];
+}
+
+impl fmt::Display for ChargeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChargeType::Percent => write!(f, "Percent"),
+            ChargeType::Fixed => write!(f, "Fixed amount"),
+            ChargeType::PerGuest => write!(f, "Per guest"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AddServiceChargeForm {
     service_charge_id: validator::Input,
     service_charge_name: String,
-    percent: validator::Input,
+    charge_type: ChargeType,
+    amount: validator::Input,
+    base: ChargeBase,
+    order: validator::Input,
     tax_group: String,
-    tax_overide: bool,
+    // Exempt from every tax in the group, otherwise just from the listed tax ids
+    tax_exempt: bool,
+    exempt_tax_ids: String,
 }
 
 #[derive(Debug, Clone)]
@@ -33,9 +69,13 @@ pub enum Message {
 
     ServiceChargeIdChanged(validator::Message),
     ServiceChargeNameChanged(String),
-    PercentChanged(validator::Message),
+    ChargeTypeChanged(ChargeType),
+    AmountChanged(validator::Message),
+    BaseChanged(ChargeBase),
+    OrderChanged(validator::Message),
     TaxGroupChanged(String),
-    TaxOverideChanged(bool),
+    TaxExemptChanged(bool),
+    ExemptTaxIdsChanged(String),
 }
 
 pub enum Action {
@@ -48,9 +88,20 @@ impl AddServiceChargeForm {
         AddServiceChargeForm {
             service_charge_id: validator::Input::default(),
             service_charge_name: String::new(),
-            percent: validator::Input::default(),
+            charge_type: ChargeType::default(),
+            amount: validator::Input::default(),
+            base: ChargeBase::default(),
+            order: validator::Input::default(),
             tax_group: String::new(),
-            tax_overide: false,
+            tax_exempt: false,
+            exempt_tax_ids: String::new(),
+        }
+    }
+
+    fn validate_amount(&self) -> fn(&str) -> bool {
+        match self.charge_type {
+            ChargeType::Percent => validate_percent,
+            ChargeType::Fixed | ChargeType::PerGuest => validate_money,
         }
     }
 
@@ -62,13 +113,33 @@ impl AddServiceChargeForm {
                     convert_to_i64(&state.service_charge_id.value)
                 } else { 0 };
 
-                let percent: f64 = if validate_f64(&state.percent.value)
+                let amount: Money = if validate_money(&state.amount.value)
+                {
+                    convert_to_money(&state.amount.value)
+                } else { Money::ZERO };
+
+                let value = match state.charge_type {
+                    ChargeType::Percent => ChargeValue::Percent(if validate_percent(&state.amount.value)
+                    {
+                        convert_percent_to_rate(&state.amount.value)
+                    } else { Rate::ZERO }),
+                    ChargeType::Fixed => ChargeValue::Fixed(amount),
+                    ChargeType::PerGuest => ChargeValue::PerGuest(amount),
+                };
+
+                let order: i32 = if validate_i32(&state.order.value)
                 {
-                    convert_to_f64(&state.percent.value)
-                } else { 0.0 };
+                    convert_to_i32(&state.order.value)
+                } else { 0 };
 
                 let service_charge_name = &state.service_charge_name;
 
+                let exempt_taxes = if state.tax_exempt {
+                    ExemptTaxes::All
+                } else {
+                    state.exempt_tax_ids.parse::<ExemptTaxes>().unwrap_or_default()
+                };
+
                 //create sales tax to add to tax group
                 let sales_tax = Tax::default();
 
@@ -79,7 +150,7 @@ impl AddServiceChargeForm {
                 //create new taxgroup with the sales tax
                 let tax_group = TaxGroup::new(1, "Default".to_string(), taxes);
 
-                let new_service_charge = ServiceCharge::new(service_charge_id, service_charge_name.to_string(),  percent, tax_group, state.tax_overide);
+                let new_service_charge = ServiceCharge::new(service_charge_id, service_charge_name.to_string(), value, state.base, order, tax_group, exempt_taxes);
 
                 Some(Action::AddNewServiceCharge(new_service_charge))
             }
@@ -113,36 +184,74 @@ impl AddServiceChargeForm {
 
                 None
             }
-            Message::PercentChanged(percent) => {
-                println!("percent Changed");
-                match percent {
+            Message::ChargeTypeChanged(charge_type) => {
+                state.charge_type = charge_type;
+                state.amount.is_valid = state.amount.value.is_empty()
+                    || validator::validate(&state.amount.value, state.validate_amount());
+
+                None
+            }
+            Message::AmountChanged(amount) => {
+                let validate_amount = state.validate_amount();
+                match amount {
                     validator::Message::RawInput(input) => {
-                        state.percent.value = input;
-                        state.percent.is_valid =
-                            validator::validate(&state.percent.value, validate_f64);
+                        state.amount.value = input;
+                        state.amount.is_valid =
+                            validator::validate(&state.amount.value, validate_amount);
                         
-                        if !state.percent.is_valid { 
-                            state.percent.value = String::new();
-                            state.percent.is_valid = true;
-                            state.percent.placeholder = "Numbers Only".to_string();
-                        } else { state.percent.placeholder = "".to_string() }
+                        if !state.amount.is_valid { 
+                            state.amount.value = String::new();
+                            state.amount.is_valid = true;
+                            state.amount.placeholder = "Numbers Only".to_string();
+                        } else { state.amount.placeholder = "".to_string() }
                     }
                     validator::Message::RawSubmit(input) => {
-                        state.percent.value = input;
-                        state.percent.is_valid =
-                            validator::validate(&state.percent.value, validate_f64);
+                        state.amount.value = input;
+                        state.amount.is_valid =
+                            validator::validate(&state.amount.value, validate_amount);
                     }
                 }
             
                 None
             }
+            Message::BaseChanged(base) => {
+                state.base = base;
+
+                None
+            }
+            Message::OrderChanged(order) => {
+                match order {
+                    validator::Message::RawInput(input) => {
+                        state.order.value = input;
+                        state.order.is_valid =
+                            validator::validate(&state.order.value, validate_i32);
+
+                        if !state.order.is_valid { 
+                            state.order.value = String::new();
+                            state.order.is_valid = true;
+                            state.order.placeholder = "Numbers Only".to_string();
+                        } else { state.order.placeholder = "".to_string() }
+                    }
+                    validator::Message::RawSubmit(input) => {
+                        state.order.value = input;
+                        state.order.is_valid =
+                            validator::validate(&state.order.value, validate_i32);
+                    }
+                }
+
+                None
+            }
             Message::TaxGroupChanged(taxgroup) => {
                 state.tax_group = taxgroup;
 
                 None
             }
-            Message::TaxOverideChanged(taxoveride) => {
-                state.tax_overide = taxoveride;
+            Message::TaxExemptChanged(tax_exempt) => {
+                state.tax_exempt = tax_exempt;
+                None
+            }
+            Message::ExemptTaxIdsChanged(exempt_tax_ids) => {
+                state.exempt_tax_ids = exempt_tax_ids;
                 None
             }
         }
@@ -150,6 +259,19 @@ impl AddServiceChargeForm {
 
 
     pub fn view<'a>(state: &Self) -> Element<'static, Message>{    
+        let amount_label = match state.charge_type {
+            ChargeType::Percent => "Percent",
+            ChargeType::Fixed => "Amount",
+            ChargeType::PerGuest => "Amount Per Guest",
+        };
+
+        // Only a percent is worked out on a base
+        let mut base_column = column![];
+        if state.charge_type == ChargeType::Percent {
+            base_column = base_column.push(text("Calculated On").size(18));
+            base_column = base_column.push(pick_list(ChargeBase::ALL, Some(state.base), Message::BaseChanged).width(120));
+        }
+
         Container::new(
             column![
                 row![
@@ -165,15 +287,24 @@ impl AddServiceChargeForm {
                     text_input("", &state.service_charge_name).on_input(Message::ServiceChargeNameChanged).id(format!("1")).width(120)
                     ].padding(8),
                 column![
-                    text("percent").size(18),
-                    validator::view(&state.percent.value, &state.percent.placeholder, state.percent.is_valid).map(Message::PercentChanged),
+                    text("Charge Type").size(18),
+                    pick_list(ChargeType::ALL, Some(state.charge_type), Message::ChargeTypeChanged).width(120),
+                    text(amount_label).size(14),
+                    validator::view(&state.amount.value, &state.amount.placeholder, state.amount.is_valid).map(Message::AmountChanged),
+                ].padding(8),
+                base_column.padding(8),
+                column![
+                    text("Order").size(18),
+                    validator::view(&state.order.value, &state.order.placeholder, state.order.is_valid).map(Message::OrderChanged),
                 ].padding(8),
                 column![
                     text("Tax Group").size(18),
                     text_input("", &state.tax_group).on_input(Message::TaxGroupChanged).width(120)
                     ].padding(8),
                 column![
-                    checkbox("Tax Overide", state.tax_overide).on_toggle(Message::TaxOverideChanged).spacing(4)
+                    checkbox("Tax Exempt", state.tax_exempt).on_toggle(Message::TaxExemptChanged).spacing(4),
+                    text("Exempt Tax IDs").size(14),
+                    text_input("2, 3", &state.exempt_tax_ids).on_input(Message::ExemptTaxIdsChanged).width(120)
                     ].spacing(8).padding(8),
                 row![
                     iced::widget::horizontal_space().width(Length::Fill),
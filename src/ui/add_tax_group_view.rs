@@ -1,8 +1,10 @@
-use iced::{Element, Length};
-use iced::widget::{button, column, row, text, text_input, Container};
+use iced::{Color, Element, Length};
+use iced::widget::{button, checkbox, column, pick_list, row, text, text_input, Column, Container};
 
 use crate::core::{
-    tax::Tax,
+    money::Rate,
+    rounding::GroupRounding,
+    tax::{Tax, TaxBase, TaxRate},
     tax_group::TaxGroup,
     calculations::{
         validate_i64,
@@ -17,7 +19,55 @@ use custom_appearances::validator;
 pub struct AddTaxGroupForm {
     tax_group_id: validator::Input,
     tax_group_name: String,
-    tax_group_taxes: String,
+    taxes: Vec<TaxFields>,
+    tax_inclusive: bool,
+    rounding: GroupRounding,
+    // Why the last submit was turned down
+    error: Option<String>,
+}
+
+// One tax of the group as typed, checked on submit. The tax group table edits
+// taxes through the same fields.
+#[derive(Debug, Clone, Default)]
+pub struct TaxFields {
+    pub id: String,
+    pub name: String,
+    pub rate: String,
+    pub order: String,
+    pub base: TaxBase,
+    // Past rates of a tax being edited, carried over untouched
+    history: Vec<TaxRate>,
+}
+
+impl TaxFields {
+    pub fn from_tax(tax: &Tax) -> Self {
+        TaxFields {
+            id: tax.id.to_string(),
+            name: tax.name.clone(),
+            rate: tax.rate.to_string(),
+            order: tax.order.to_string(),
+            base: tax.base,
+            history: tax.history.clone(),
+        }
+    }
+
+    // `number` counts from 1 and names the tax in the error
+    pub fn to_tax(&self, number: usize) -> Result<Tax, String> {
+        let id = self.id.trim().parse::<i64>()
+            .map_err(|_| format!("Tax {}: the ID must be a whole number", number))?;
+        let rate = self.rate.parse::<Rate>()
+            .map_err(|error| format!("Tax {}: the rate is {}", number, error))?;
+        let order = match self.order.trim() {
+            "" => 0,
+            order => order.parse::<i32>().map_err(|_| format!("Tax {}: the order must be a whole number", number))?,
+        };
+
+        let mut tax = Tax::new(id, self.name.clone(), rate);
+        tax.order = order;
+        tax.base = self.base;
+        tax.history = self.history.clone();
+        Ok(tax)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,7 +76,16 @@ pub enum Message {
 
     TaxGroupIdChanged(validator::Message),
     TaxGroupNameChanged(String),
-    TaxGroupTaxesChanged(String,)
+    TaxInclusiveChanged(bool),
+    RoundingChanged(GroupRounding),
+
+    AddTax,
+    RemoveTax(usize),
+    TaxIdChanged(usize, String),
+    TaxNameChanged(usize, String),
+    TaxRateChanged(usize, String),
+    TaxOrderChanged(usize, String),
+    TaxBaseChanged(usize, TaxBase),
 }
 
 pub enum Action {
@@ -39,7 +98,10 @@ impl AddTaxGroupForm {
         AddTaxGroupForm {
             tax_group_id: validator::Input::default(),
             tax_group_name: String::new(),
-            tax_group_taxes: String::new(),
+            taxes: vec![TaxFields::default()],
+            tax_inclusive: false,
+            rounding: GroupRounding::Receipt,
+            error: None,
         }
     }
 
@@ -53,22 +115,27 @@ impl AddTaxGroupForm {
 
                 let tax_group_name = state.tax_group_name.clone();
 
-                //let tax_group_tax = state.tax_group_taxes.clone();
+                // Nothing is added until every tax reads correctly
+                let taxes: Result<Vec<Tax>, String> = state.taxes.iter()
+                    .enumerate()
+                    .map(|(index, fields)| fields.to_tax(index + 1))
+                    .collect();
 
-                if parse_taxes(state.tax_group_taxes.clone()).is_ok() {
-                    let taxes = parse_taxes(state.tax_group_taxes.clone()).ok().unwrap();
-                    let new_tax_group = TaxGroup::new(tax_group_id, tax_group_name, taxes);
+                match taxes {
+                    Ok(taxes) => {
+                        state.error = None;
+                        let mut new_tax_group = TaxGroup::new(tax_group_id, tax_group_name, taxes);
+                        new_tax_group.tax_inclusive = state.tax_inclusive;
+                        new_tax_group.rounding = state.rounding.rounding();
 
-                    Some(Action::AddNewTaxGroup(new_tax_group))
-                } else {
-                    let mut tax_vec = Vec::new();
-                    let default_tax = Tax::default();
-                    tax_vec.push(default_tax);
-                    let new_tax_group = TaxGroup::new(tax_group_id, tax_group_name, tax_vec);
+                        Some(Action::AddNewTaxGroup(new_tax_group))
+                    }
+                    Err(error) => {
+                        state.error = Some(error);
 
-                    Some(Action::AddNewTaxGroup(new_tax_group))
+                        None
+                    }
                 }
-
             }
             Message::TaxGroupIdChanged(id) => {
                 match id {
@@ -98,17 +165,61 @@ impl AddTaxGroupForm {
 
                 None
             }
-            Message::TaxGroupTaxesChanged(taxes) => {
-                let cloned_taxes = taxes.clone();
+            Message::TaxInclusiveChanged(tax_inclusive) => {
+                state.tax_inclusive = tax_inclusive;
 
-                match parse_taxes(cloned_taxes) {
-                    Ok(parsed_taxes) => {
-                        state.tax_group_taxes = taxes;
-                    }
-                    Err(e) => {
-                        //store error?
-                    }
+                None
+            }
+            Message::RoundingChanged(rounding) => {
+                state.rounding = rounding;
+
+                None
+            }
+            Message::AddTax => {
+                state.taxes.push(TaxFields::default());
+
+                None
+            }
+            Message::RemoveTax(index) => {
+                if index < state.taxes.len() {
+                    state.taxes.remove(index);
+                }
+
+                None
+            }
+            Message::TaxIdChanged(index, id) => {
+                if let Some(fields) = state.taxes.get_mut(index) {
+                    fields.id = id;
                 }
+
+                None
+            }
+            Message::TaxNameChanged(index, name) => {
+                if let Some(fields) = state.taxes.get_mut(index) {
+                    fields.name = name;
+                }
+
+                None
+            }
+            Message::TaxRateChanged(index, rate) => {
+                if let Some(fields) = state.taxes.get_mut(index) {
+                    fields.rate = rate;
+                }
+
+                None
+            }
+            Message::TaxOrderChanged(index, order) => {
+                if let Some(fields) = state.taxes.get_mut(index) {
+                    fields.order = order;
+                }
+
+                None
+            }
+            Message::TaxBaseChanged(index, base) => {
+                if let Some(fields) = state.taxes.get_mut(index) {
+                    fields.base = base;
+                }
+
                 None
             }
         }
@@ -117,49 +228,132 @@ impl AddTaxGroupForm {
 
     pub fn view<'a>(state: &Self) -> Element<'static, Message>{
 
-        //create sales tax to add to tax group
-        let sales_tax = Tax::default();
-    
-        //add sales tax to a new Vec 'taxes'
-        let mut taxes = Vec::new();
-        &taxes.push(sales_tax);
-    
+        let mut taxes = Column::new().spacing(8);
+        for (index, fields) in state.taxes.iter().enumerate() {
+            taxes = taxes.push(column![
+                row![
+                    text(format!("Tax {}", index + 1)).size(16).width(Length::Fill),
+                    button("Remove").on_press(Message::RemoveTax(index)),
+                ],
+                text_input("ID", &fields.id).on_input(move |id| Message::TaxIdChanged(index, id)).width(120),
+                text_input("Name", &fields.name).on_input(move |name| Message::TaxNameChanged(index, name)).width(120),
+                text_input("Rate, 7.75%", &fields.rate).on_input(move |rate| Message::TaxRateChanged(index, rate)).width(120),
+                text_input("Order", &fields.order).on_input(move |order| Message::TaxOrderChanged(index, order)).width(120),
+                pick_list(TaxBase::ALL, Some(fields.base), move |base| Message::TaxBaseChanged(index, base)).width(120),
+            ].spacing(4));
+        }
+
+        let mut form = column![
+            row![
+                text("Add Tax Group").size(16),
+            ].padding(8),
+            iced::widget::horizontal_rule(1),
+            column![
+                text("ID").size(18),
+                validator::view(&state.tax_group_id.value.clone(), &state.tax_group_id.placeholder.clone(), true).map(Message::TaxGroupIdChanged),
+            ].padding(8),
 
-        Container::new(
-        column![
             column![
-                row![
-                    text("Add Tax Group").size(16),
-                ].padding(8),
-                iced::widget::horizontal_rule(1),
-                column![
-                    text("ID").size(18),
-                    validator::view(&state.tax_group_id.value.clone(), &state.tax_group_id.placeholder.clone(), true).map(Message::TaxGroupIdChanged),
-                ].padding(8),
-
-                column![
-                    text("Name").size(18),
-                    text_input("", &state.tax_group_name).on_input(Message::TaxGroupNameChanged).width(120),
-                ].padding(8),
-                column![
-                    text("Taxes").size(18),
-                    text_input("", &state.tax_group_taxes).on_input(Message::TaxGroupTaxesChanged).width(120),
-                ].padding(8),
+                text("Name").size(18),
+                text_input("", &state.tax_group_name).on_input(Message::TaxGroupNameChanged).width(120),
+            ].padding(8),
+            column![
+                text("Taxes").size(18),
+                taxes,
+                button("Add Tax").on_press(Message::AddTax),
+            ].spacing(8).padding(8),
+            column![
+                checkbox("Tax Inclusive", state.tax_inclusive).on_toggle(Message::TaxInclusiveChanged).spacing(4)
+            ].spacing(8).padding(8),
+            column![
+                text("Rounding").size(18),
+                pick_list(GroupRounding::options(), Some(state.rounding), Message::RoundingChanged).width(120),
+            ].padding(8),
+        ];
+
+        if let Some(error) = &state.error {
+            form = form.push(
+                text(error.clone()).size(14).color(Color::from_rgb(0.8, 0.1, 0.1))
+            );
+        }
+
+        Container::new(
+            form.push(
                 row![
                     iced::widget::horizontal_space().width(Length::Fill),
                     button("Submit").on_press(Message::Submit).width(Length::Shrink),
                     iced::widget::horizontal_space().width(Length::Fill),
                 ].padding(8).width(130),
-            ]
-        ]
+            )
         )
         .width(130)
         .into()
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::date::Date;
+    use crate::core::money::RoundingMode;
+    use crate::core::rounding::{RoundingLevel, TaxRounding};
+
+    fn fill(form: &mut AddTaxGroupForm, index: usize, id: &str, name: &str, rate: &str) {
+        AddTaxGroupForm::update(form, Message::TaxIdChanged(index, id.to_string()));
+        AddTaxGroupForm::update(form, Message::TaxNameChanged(index, name.to_string()));
+        AddTaxGroupForm::update(form, Message::TaxRateChanged(index, rate.to_string()));
+    }
+
+    #[test]
+    fn builds_ordered_and_compound_taxes() {
+        let mut form = AddTaxGroupForm::new();
+        fill(&mut form, 0, "1", "GST", "5");
+        AddTaxGroupForm::update(&mut form, Message::AddTax);
+        fill(&mut form, 1, "2", "QST", "9.975%");
+        AddTaxGroupForm::update(&mut form, Message::TaxOrderChanged(1, "1".to_string()));
+        AddTaxGroupForm::update(&mut form, Message::TaxBaseChanged(1, TaxBase::PriceAndEarlierTaxes));
+
+        let Some(Action::AddNewTaxGroup(tax_group)) = AddTaxGroupForm::update(&mut form, Message::Submit) else {
+            panic!("the tax group wasn't added");
+        };
+        assert_eq!(tax_group.taxes.len(), 2);
+        assert_eq!(tax_group.taxes[1].order, 1);
+        assert_eq!(tax_group.taxes[1].base, TaxBase::PriceAndEarlierTaxes);
+        assert_eq!(tax_group.rounding, None);
+    }
+
+    #[test]
+    fn gives_the_group_its_own_rounding() {
+        let mut form = AddTaxGroupForm::new();
+        fill(&mut form, 0, "1", "GST", "5");
+        let rounding = TaxRounding::new(RoundingMode::HalfEven, RoundingLevel::PerInvoice);
+        AddTaxGroupForm::update(&mut form, Message::RoundingChanged(GroupRounding::Own(rounding)));
 
-fn parse_taxes(tax_string: String) -> Result<Vec<Tax>, serde_json::Error> {
-    // Attempt to parse the JSON string into Vec<Tax>
-    serde_json::from_str(&tax_string)
-}
\ No newline at end of file
+        let Some(Action::AddNewTaxGroup(tax_group)) = AddTaxGroupForm::update(&mut form, Message::Submit) else {
+            panic!("the tax group wasn't added");
+        };
+        assert_eq!(tax_group.rounding, Some(rounding));
+    }
+
+    #[test]
+    fn shows_why_a_tax_is_turned_down() {
+        let mut form = AddTaxGroupForm::new();
+        fill(&mut form, 0, "1", "Sales", "seven");
+
+        assert!(AddTaxGroupForm::update(&mut form, Message::Submit).is_none());
+        assert_eq!(form.error.as_deref(), Some("Tax 1: the rate is not a number"));
+    }
+
+    #[test]
+    fn keeps_the_rate_history_of_an_edited_tax() {
+        let mut tax = Tax::new(1, "Sales".to_string(), "5".parse().unwrap());
+        tax.set_rate("6".parse().unwrap(), Date::new(2024, 1, 1).unwrap());
+
+        let mut fields = TaxFields::from_tax(&tax);
+        fields.name = "State Sales".to_string();
+        let edited = fields.to_tax(1).unwrap();
+        assert_eq!(edited.name, "State Sales");
+        assert_eq!(edited.rate, tax.rate);
+        assert_eq!(edited.history, tax.history);
+    }
+}
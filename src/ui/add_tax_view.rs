@@ -2,10 +2,11 @@ use iced::{Element, Length, Task};
 use iced::widget::{button, checkbox, column, row, text, text_input, Container};
 
 use crate::core::{
+    money::Rate,
     tax::Tax,
     calculations::{
-        validate_f64,
-        convert_to_f64,
+        validate_percent,
+        convert_percent_to_rate,
         validate_i64,
         convert_to_i64
     },
@@ -44,6 +45,13 @@ impl AddTaxForm {
         }
     }
 
+    // Start the form from a rate, such as one suggested by the solver
+    pub fn with_rate(rate: Rate) -> Self {
+        let mut form = AddTaxForm::new();
+        form.percent.value = rate.to_string();
+        form
+    }
+
     pub fn update(state: &mut Self, message: Message) -> Option<Action> {
         match message {
             Message::Submit => {
@@ -52,10 +60,10 @@ impl AddTaxForm {
                     convert_to_i64(&state.tax_id.value)
                 } else { 0 };
 
-                let tax_percent: f64 = if validate_f64(&state.percent.value)
+                let tax_percent: Rate = if validate_percent(&state.percent.value)
                 {
-                    convert_to_f64(&state.percent.value)
-                } else { 0.0 };
+                    convert_percent_to_rate(&state.percent.value)
+                } else { Rate::ZERO };
 
                 let tax_name = &state.tax_name;
 
@@ -99,7 +107,7 @@ impl AddTaxForm {
                     validator::Message::RawInput(input) => {
                         state.percent.value = input;
                         state.percent.is_valid =
-                            validator::validate(&state.percent.value, validate_f64);
+                            validator::validate(&state.percent.value, validate_percent);
 
                         if !state.percent.is_valid { 
                             state.percent.value = String::new();
@@ -110,7 +118,7 @@ impl AddTaxForm {
                     validator::Message::RawSubmit(input) => {
                         state.percent.value = input;
                         state.percent.is_valid =
-                            validator::validate(&state.percent.value, validate_f64);
+                            validator::validate(&state.percent.value, validate_percent);
 
                     }
                 }
@@ -0,0 +1,107 @@
+use iced::{Element, Length, Task};
+use iced::widget::{column, text};
+
+use crate::core::currency::MoneyFormat;
+use crate::core::discount::{Discount, DiscountValue, create_discount_table};
+
+#[derive(Debug, Clone)]
+pub struct DiscountView {
+    discounts: Vec<Discount>,
+    edit_states: std::collections::HashMap<i64, DiscountEditState>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    //modify discount list and states
+    DeleteDiscount(i64),
+    SaveDiscount(i64),
+    NewDiscount(Discount),
+    ToggleEditMode(i64, bool),
+    EditField(i64, String, String)
+}
+
+impl DiscountView {
+
+    pub fn new() -> Self {
+        DiscountView {
+            discounts: vec![Discount::default()],
+            edit_states: std::collections::HashMap::new(),
+        }
+    }
+
+    pub fn discounts(&self) -> &[Discount] {
+        &self.discounts
+    }
+
+    pub fn update(state: &mut Self, message: Message, format: MoneyFormat) -> Task<Message> {
+        match message {
+            //modify discount list and states
+            Message::DeleteDiscount(discount_id) => {
+                state.discounts.retain(|x| x.id != discount_id);
+                state.edit_states.remove(&discount_id);
+
+                Task::none()
+            }
+            Message::SaveDiscount(discount_id) => {
+                if let Some(discount) = state.discounts.iter_mut().find(|i| i.id == discount_id) {
+                    if let Some(edit_state) = state.edit_states.get(&discount_id) {
+                        discount.name = edit_state.name.clone();
+                        if let Ok(value) = DiscountValue::parse(&edit_state.value, &format) {
+                            discount.value = value;
+                        }
+                    }
+                }
+
+                Task::done(Message::ToggleEditMode(discount_id, false))
+            }
+            Message::NewDiscount(new_discount) => {
+                state.discounts.push(new_discount);
+
+                Task::none()
+            }
+            Message::ToggleEditMode(discount_id, editing) => {
+
+                if editing {
+                    if let Some(discount) = state.discounts.iter().find(|i| i.id == discount_id) {
+                        state.edit_states.insert(discount_id, DiscountEditState {
+                            name: discount.name.clone(),
+                            value: discount.value.describe(&format),
+                            is_editing: true,
+                        });
+                    }
+                } else {
+                    state.edit_states.remove(&discount_id);
+                }
+
+                Task::none()
+            }
+            Message::EditField(discount_id, field_name, new_value) => {
+                if let Some(edit_state) = state.edit_states.get_mut(&discount_id) {
+                    match field_name.as_str() {
+                        "name" => edit_state.name = new_value,
+                        "value" => edit_state.value = new_value,
+                        _ => {}
+                    }
+                }
+
+                Task::none()
+            }
+        }
+    }
+
+    pub fn view(state: &Self, format: MoneyFormat) -> Element<'static, Message>{
+
+        column![
+            text("Discounts").size(25),
+            create_discount_table(state.discounts.clone(), format, &mut state.edit_states.clone())
+        ].height(Length::Fill).padding(5).into()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscountEditState {
+    pub name: String,
+    // "10%" or "$5.00", written in the app's money format
+    pub value: String,
+    pub is_editing: bool,
+}
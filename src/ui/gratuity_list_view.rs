@@ -1,6 +1,9 @@
 use iced::{Element, Length, Task};
 use iced::widget::{column, text};
 
+use crate::core::exemption::ExemptTaxes;
+use crate::core::money::Rate;
+use crate::core::currency::MoneyFormat;
 use crate::core::gratuity::{Gratuity, create_gratuity_table};
 
 #[derive(Debug, Clone)]
@@ -32,6 +35,10 @@ impl GratuityView {
         }
     }
 
+    pub fn gratuities(&self) -> &[Gratuity] {
+        &self.gratuitys
+    }
+
     pub fn update(state: &mut Self, message: Message) -> Task<Message> {
         match message {
             //modify gratuity list and states
@@ -45,8 +52,14 @@ impl GratuityView {
                 if let Some(gratuity) = state.gratuitys.iter_mut().find(|i| i.id == gratuity_id) {
                     if let Some(edit_state) = state.edit_states.get(&gratuity_id) {
                         gratuity.name = edit_state.name.clone();
-                        if let Ok(percent) = edit_state.percent.parse::<f64>() {
-                            gratuity.percent = percent;
+                        if let Ok(exempt_taxes) = edit_state.exempt_taxes.parse::<ExemptTaxes>() {
+                            gratuity.exempt_taxes = exempt_taxes;
+                        }
+                        if let Ok(percent) = edit_state.percent.parse::<Rate>() {
+                            gratuity.rate = percent;
+                        }
+                        if let Ok(order) = edit_state.order.parse::<i32>() {
+                            gratuity.order = order;
                         }
                     }
                 }
@@ -64,8 +77,9 @@ impl GratuityView {
                     if let Some(gratuity) = state.gratuitys.iter().find(|i| i.id == gratuity_id) {
                         state.edit_states.insert(gratuity_id, GratuityEditState {
                             name: gratuity.name.clone(),
-                            percent: gratuity.percent.to_string(),
-                            tax_overide: gratuity.tax_overide,
+                            percent: gratuity.rate.to_string(),
+                            order: gratuity.order.to_string(),
+                            exempt_taxes: gratuity.exempt_taxes.to_string(),
                             is_editing: true,
                         });
                     } 
@@ -80,6 +94,8 @@ impl GratuityView {
                     match field_name.as_str() {
                         "name" => edit_state.name = new_value,
                         "percent" => edit_state.percent = new_value,
+                        "order" => edit_state.order = new_value,
+                        "exempt_taxes" => edit_state.exempt_taxes = new_value,
                         _ => {}
                     }
                 }
@@ -89,11 +105,11 @@ impl GratuityView {
         }
     }
 
-    pub fn view<'a>(state: &Self) -> Element<'static, Message>{
+    pub fn view<'a>(state: &Self, format: MoneyFormat) -> Element<'static, Message>{
 
         column![
             text("Gratuitiess").size(25),
-            create_gratuity_table(state.gratuitys.clone(), &mut state.edit_states.clone())
+            create_gratuity_table(state.gratuitys.clone(), format, &mut state.edit_states.clone())
         ].height(Length::Fill).padding(5).into()
     }
 }
@@ -102,6 +118,7 @@ impl GratuityView {
 pub struct GratuityEditState {
     pub name: String,
     pub percent: String,
-    pub tax_overide: bool,
+    pub order: String,
+    pub exempt_taxes: String,
     pub is_editing: bool,
 }
\ No newline at end of file
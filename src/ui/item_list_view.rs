@@ -1,6 +1,8 @@
 use iced::{Element, Length, Task};
 use iced::widget::{column, text};
 
+use crate::core::exemption::ExemptTaxes;
+use crate::core::currency::MoneyFormat;
 use crate::core::items::{Item, create_items_table};
 
 #[derive(Debug, Clone)]
@@ -32,7 +34,11 @@ impl ItemView {
         }
     }
 
-    pub fn update(state: &mut Self, message: Message) -> Task<Message> {
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn update(state: &mut Self, message: Message, format: MoneyFormat) -> Task<Message> {
         match message {
             //modify item list and states
             Message::DeleteItem(item_id) => {
@@ -45,7 +51,11 @@ impl ItemView {
                 if let Some(item) = state.items.iter_mut().find(|i| i.id == item_id) {
                     if let Some(edit_state) = state.edit_states.get(&item_id) {
                         item.name = edit_state.name.clone();
-                        if let Ok(price) = edit_state.price.parse::<f64>() {
+                        item.category = edit_state.category.clone();
+                        if let Ok(exempt_taxes) = edit_state.exempt_taxes.parse::<ExemptTaxes>() {
+                            item.exempt_taxes = exempt_taxes;
+                        }
+                        if let Ok(price) = format.parse(&edit_state.price) {
                             item.price = price;
                         }
                     }
@@ -64,9 +74,9 @@ impl ItemView {
                     if let Some(item) = state.items.iter().find(|i| i.id == item_id) {
                         state.edit_states.insert(item_id, ItemEditState {
                             name: item.name.clone(),
-                            price: item.price.to_string(),
+                            price: format.format(item.price),
                             category: item.category.clone(),
-                            tax_overide: item.tax_overide,
+                            exempt_taxes: item.exempt_taxes.to_string(),
                             is_editing: true,
                         });
                     } 
@@ -82,6 +92,7 @@ impl ItemView {
                         "name" => edit_state.name = new_value,
                         "price" => edit_state.price = new_value,
                         "category" => edit_state.category = new_value,
+                        "exempt_taxes" => edit_state.exempt_taxes = new_value,
                         _ => {}
                     }
                 }
@@ -91,11 +102,11 @@ impl ItemView {
         }
     }
 
-    pub fn view<'a>(state: &Self) -> Element<'static, Message>{
+    pub fn view<'a>(state: &Self, format: MoneyFormat) -> Element<'static, Message>{
 
         column![
             text("Items").size(25),
-            create_items_table(state.items.clone(), &mut state.edit_states.clone())
+            create_items_table(state.items.clone(), format, &mut state.edit_states.clone())
         ].height(Length::Fill).padding(5).into()
     }
 }
@@ -105,6 +116,6 @@ pub struct ItemEditState {
     pub name: String,
     pub price: String,
     pub category: String,
-    pub tax_overide: bool,
+    pub exempt_taxes: String,
     pub is_editing: bool,
 }
\ No newline at end of file
@@ -0,0 +1,1011 @@
+use std::path::Path;
+
+use iced::{Color, Element, Length, Task};
+use iced::widget::{button, checkbox, column, pick_list, row, scrollable, text, text_input, Column};
+
+use crate::core::{
+    breakdown::ReceiptBreakdown,
+    currency::{Currency, ExchangeRates, MoneyFormat},
+    date::Date,
+    discount::Discount,
+    exemption::{exemption_report, ExemptTaxes, ExemptionRecord, TaxExemption},
+    gratuity::Gratuity,
+    items::Item,
+    minimum_spend::MinimumSpend,
+    modifier::Modifier,
+    payment::{Payment, Tender},
+    money::{Money, Rate, RoundingMode},
+    quantity::Quantity,
+    receipt::Receipt,
+    reconcile::{reconcile, PrintedReceipt, PrintedTax, Reconciliation},
+    rounding::{CashRounding, RoundingLevel, RoundingPolicy},
+    service_charge::ServiceCharge,
+    tax_group::TaxGroup,
+    split::{split, split_evenly, GuestShare, SplitPlan},
+    solver::{catalog::{match_catalog, CatalogMatch, SearchLimits}, infer_tax_rate, solve, RateInference, Solution, SolveError, SolveLimits},
+    tax::Tax,
+};
+
+// What the receipt is rung up from, kept by the other views
+pub struct Catalog<'a> {
+    pub items: &'a [Item],
+    pub service_charges: &'a [ServiceCharge],
+    pub gratuities: &'a [Gratuity],
+    pub discounts: &'a [Discount],
+    pub taxes: &'a [Tax],
+    pub format: MoneyFormat,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReceiptView {
+    receipt_id: i32,
+    lines: Vec<OrderLine>,
+    item_name: Option<String>,
+    quantity: String,
+    // The add-on as typed, added to a line with its button
+    modifier_name: String,
+    modifier_price: String,
+    modifier_untaxed: bool,
+
+    guests: String,
+    date: String,
+    rounding: RoundingPolicy,
+    // The app's currency until one is picked for this receipt
+    currency: Option<Currency>,
+    rates_path: String,
+    rates: Option<ExchangeRates>,
+    // Banquet minimum, the categories that count are comma separated
+    minimum_spend: String,
+    minimum_categories: String,
+    // The customer's exemption, set once any tax is ticked
+    customer: String,
+    certificate: String,
+    exempt_tax_ids: Vec<i64>,
+    // Paid so far, with the next payment as typed
+    payments: Vec<Payment>,
+    tender: Tender,
+    payment_amount: String,
+    payment_tip: String,
+
+    split_guests: String,
+    split_by_seat: bool,
+
+    // The venue's printed figures, any left blank aren't compared
+    printed_subtotal: String,
+    printed_taxes: Vec<PrintedTax>,
+    printed_tax_name: String,
+    printed_tax_amount: String,
+    printed_service_charge: String,
+    printed_gratuity: String,
+    printed_total: String,
+
+    target: String,
+    tolerance: String,
+    // Searches run off the screen and are dropped once the receipt changes
+    searching: bool,
+    solutions: Option<Result<Vec<Solution>, SolveError>>,
+    inference: Option<RateInference>,
+    matches: Option<Vec<CatalogMatch>>,
+
+    // Worked out again after every change, the view only shows them
+    receipt: Option<Receipt>,
+    breakdown: Option<ReceiptBreakdown>,
+    // What each guest owes, once a number of guests is typed
+    shares: Option<Result<Vec<GuestShare>, String>>,
+    // The total in the home currency of the rates loaded
+    home_total: Option<Result<Money, String>>,
+    // How the printed figures compare, once any is typed
+    reconciliation: Option<Result<Reconciliation, String>>,
+    // Why the receipt as typed can't be built
+    build_error: Option<String>,
+    // Why the last action was turned down
+    error: Option<String>,
+
+    // Receipts closed so far and the exempt ones among them
+    closed: Vec<Receipt>,
+    exemptions: Vec<ExemptionRecord>,
+}
+
+#[derive(Debug, Clone)]
+struct OrderLine {
+    item: Item,
+    quantity: Quantity,
+    modifiers: Vec<Modifier>,
+    // Seat number as typed, used when splitting by seat
+    seat: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    // Sent when the screen opens, the other views may have changed the catalog
+    Refresh,
+
+    ItemChosen(String),
+    QuantityChanged(String),
+    AddLine,
+    RemoveLine(usize),
+    ModifierNameChanged(String),
+    ModifierPriceChanged(String),
+    ModifierUntaxedToggled(bool),
+    AddModifier(usize),
+    RemoveModifier(usize, usize),
+    SeatChanged(usize, String),
+
+    GuestsChanged(String),
+    DateChanged(String),
+    TaxModeChanged(RoundingMode),
+    TaxLevelChanged(RoundingLevel),
+    CashRoundingChanged(CashRounding),
+    CurrencyChanged(Currency),
+    MinimumSpendChanged(String),
+    MinimumCategoriesChanged(String),
+    RatesPathChanged(String),
+    LoadRates,
+    CustomerChanged(String),
+    CertificateChanged(String),
+    ExemptTaxToggled(i64, bool),
+    CloseReceipt,
+    TenderChosen(Tender),
+    PaymentAmountChanged(String),
+    PaymentTipChanged(String),
+    AddPayment,
+    RemovePayment(usize),
+
+    SplitGuestsChanged(String),
+    SplitBySeatToggled(bool),
+
+    PrintedSubtotalChanged(String),
+    PrintedTaxNameChanged(String),
+    PrintedTaxAmountChanged(String),
+    AddPrintedTax,
+    RemovePrintedTax(usize),
+    PrintedServiceChargeChanged(String),
+    PrintedGratuityChanged(String),
+    PrintedTotalChanged(String),
+
+    TargetChanged(String),
+    ToleranceChanged(String),
+    FindTaxedLines,
+    // The receipt searched, found ways are only kept while it is still the one rung up
+    TaxedLinesFound(Box<Receipt>, Result<Vec<Solution>, SolveError>),
+    MatchCatalog,
+    CatalogMatched(Box<Receipt>, Vec<CatalogMatch>),
+    SuggestTaxRate,
+    TaxRateSuggested(Box<Receipt>, Option<RateInference>),
+    // Opens the add tax form at the rate, handled by the app
+    CreateTax(Rate),
+}
+
+impl ReceiptView {
+    pub fn new() -> Self {
+        ReceiptView {
+            receipt_id: 1,
+            lines: Vec::new(),
+            item_name: None,
+            quantity: "1".to_string(),
+            modifier_name: String::new(),
+            modifier_price: String::new(),
+            modifier_untaxed: false,
+            guests: String::new(),
+            date: String::new(),
+            rounding: RoundingPolicy::default(),
+            currency: None,
+            rates_path: String::new(),
+            rates: None,
+            minimum_spend: String::new(),
+            minimum_categories: String::new(),
+            customer: String::new(),
+            certificate: String::new(),
+            exempt_tax_ids: Vec::new(),
+            payments: Vec::new(),
+            tender: Tender::Cash,
+            payment_amount: String::new(),
+            payment_tip: String::new(),
+            split_guests: String::new(),
+            split_by_seat: false,
+            printed_subtotal: String::new(),
+            printed_taxes: Vec::new(),
+            printed_tax_name: String::new(),
+            printed_tax_amount: String::new(),
+            printed_service_charge: String::new(),
+            printed_gratuity: String::new(),
+            printed_total: String::new(),
+            target: String::new(),
+            tolerance: String::new(),
+            searching: false,
+            solutions: None,
+            inference: None,
+            matches: None,
+            receipt: None,
+            breakdown: None,
+            shares: None,
+            home_total: None,
+            reconciliation: None,
+            build_error: None,
+            error: None,
+            closed: Vec::new(),
+            exemptions: Vec::new(),
+        }
+    }
+
+    pub fn update(state: &mut Self, message: Message, catalog: &Catalog) -> Task<Message> {
+        state.error = None;
+        let mut task = Task::none();
+
+        match message {
+            Message::Refresh => {}
+
+            Message::ItemChosen(name) => state.item_name = Some(name),
+            Message::QuantityChanged(quantity) => state.quantity = quantity,
+            Message::AddLine => {
+                let item = state.item_name.as_ref()
+                    .and_then(|name| catalog.items.iter().find(|item| &item.name == name));
+                match (item, state.quantity.parse::<Quantity>()) {
+                    (Some(item), Ok(quantity)) => {
+                        // A bare number is that many of the item's own unit
+                        let quantity = if !state.quantity.trim().ends_with(char::is_alphabetic) {
+                            Quantity::from_thousandths(quantity.thousandths(), item.unit)
+                        } else {
+                            quantity
+                        };
+                        state.lines.push(OrderLine { item: item.clone(), quantity: quantity, modifiers: Vec::new(), seat: String::new() });
+                    }
+                    (None, _) => state.error = Some("Pick an item to add".to_string()),
+                    (_, Err(error)) => state.error = Some(format!("The quantity is {}", error)),
+                }
+            }
+            Message::RemoveLine(index) => {
+                if index < state.lines.len() {
+                    state.lines.remove(index);
+                }
+            }
+            Message::ModifierNameChanged(name) => state.modifier_name = name,
+            Message::ModifierPriceChanged(price) => state.modifier_price = price,
+            Message::ModifierUntaxedToggled(untaxed) => state.modifier_untaxed = untaxed,
+            Message::AddModifier(index) => {
+                let format = state.format(catalog);
+                match (state.lines.get_mut(index), format.parse(&state.modifier_price)) {
+                    (Some(_), _) if state.modifier_name.trim().is_empty() => state.error = Some("Name the add-on first".to_string()),
+                    (Some(line), Ok(price)) => {
+                        // An add-on is taxed like the item it goes on unless it is marked untaxed
+                        let exempt_taxes = if state.modifier_untaxed { ExemptTaxes::All } else { ExemptTaxes::None };
+                        let id = line.modifiers.iter().map(|modifier| modifier.id).max().unwrap_or(0) + 1;
+                        line.modifiers.push(Modifier::new(id, state.modifier_name.trim().to_string(), price, line.item.tax_group.clone(), exempt_taxes));
+                    }
+                    (Some(_), Err(error)) => state.error = Some(format!("The add-on price is {}", error)),
+                    (None, _) => {}
+                }
+            }
+            Message::RemoveModifier(index, modifier) => {
+                if let Some(line) = state.lines.get_mut(index) {
+                    if modifier < line.modifiers.len() {
+                        line.modifiers.remove(modifier);
+                    }
+                }
+            }
+            Message::SeatChanged(index, seat) => {
+                if let Some(line) = state.lines.get_mut(index) {
+                    line.seat = seat;
+                }
+            }
+
+            Message::GuestsChanged(guests) => state.guests = guests,
+            Message::DateChanged(date) => state.date = date,
+            Message::TaxModeChanged(mode) => state.rounding.tax.mode = mode,
+            Message::TaxLevelChanged(level) => state.rounding.tax.level = level,
+            Message::CashRoundingChanged(cash) => state.rounding.cash = cash,
+            Message::CurrencyChanged(currency) => state.currency = Some(currency),
+            Message::RatesPathChanged(path) => state.rates_path = path,
+            Message::MinimumSpendChanged(minimum) => state.minimum_spend = minimum,
+            Message::MinimumCategoriesChanged(categories) => state.minimum_categories = categories,
+            Message::LoadRates => {
+                match ExchangeRates::load(Path::new(state.rates_path.trim())) {
+                    Ok(rates) => state.rates = Some(rates),
+                    Err(error) => state.error = Some(error.to_string()),
+                }
+            }
+            Message::CustomerChanged(customer) => state.customer = customer,
+            Message::CertificateChanged(certificate) => state.certificate = certificate,
+            Message::ExemptTaxToggled(tax_id, exempt) => {
+                state.exempt_tax_ids.retain(|id| *id != tax_id);
+                if exempt {
+                    state.exempt_tax_ids.push(tax_id);
+                }
+            }
+            Message::CloseReceipt => {
+                match (&state.receipt, &state.build_error) {
+                    (Some(receipt), _) if !state.lines.is_empty() => {
+                        state.closed.push(receipt.clone());
+                        state.exemptions = exemption_report(&state.closed);
+                        state.receipt_id += 1;
+                        state.lines.clear();
+                        state.customer.clear();
+                        state.certificate.clear();
+                        state.exempt_tax_ids.clear();
+                        state.payments.clear();
+                    }
+                    (_, Some(error)) => state.error = Some(error.clone()),
+                    _ => state.error = Some("Ring up the receipt first".to_string()),
+                }
+            }
+            Message::TenderChosen(tender) => state.tender = tender,
+            Message::PaymentAmountChanged(amount) => state.payment_amount = amount,
+            Message::PaymentTipChanged(tip) => state.payment_tip = tip,
+            Message::AddPayment => {
+                let format = state.format(catalog);
+                let tip = if state.payment_tip.trim().is_empty() {
+                    Ok(Money::ZERO)
+                } else {
+                    format.parse(&state.payment_tip)
+                };
+                match (format.parse(&state.payment_amount), tip) {
+                    (Ok(amount), Ok(tip)) => {
+                        let id = state.payments.iter().map(|payment| payment.id).max().unwrap_or(0) + 1;
+                        state.payments.push(Payment::new(id, state.tender, amount, tip));
+                        state.payment_amount.clear();
+                        state.payment_tip.clear();
+                    }
+                    (Err(error), _) => state.error = Some(format!("The amount paid is {}", error)),
+                    (_, Err(error)) => state.error = Some(format!("The tip is {}", error)),
+                }
+            }
+            Message::RemovePayment(index) => {
+                if index < state.payments.len() {
+                    state.payments.remove(index);
+                }
+            }
+
+            Message::SplitGuestsChanged(guests) => state.split_guests = guests,
+            Message::SplitBySeatToggled(by_seat) => state.split_by_seat = by_seat,
+
+            Message::PrintedSubtotalChanged(subtotal) => state.printed_subtotal = subtotal,
+            Message::PrintedTaxNameChanged(name) => state.printed_tax_name = name,
+            Message::PrintedTaxAmountChanged(amount) => state.printed_tax_amount = amount,
+            Message::AddPrintedTax => {
+                match state.format(catalog).parse(&state.printed_tax_amount) {
+                    _ if state.printed_tax_name.trim().is_empty() => state.error = Some("Name the printed tax first".to_string()),
+                    Ok(amount) => {
+                        state.printed_taxes.push(PrintedTax::new(state.printed_tax_name.trim().to_string(), amount));
+                        state.printed_tax_name.clear();
+                        state.printed_tax_amount.clear();
+                    }
+                    Err(error) => state.error = Some(format!("The printed tax is {}", error)),
+                }
+            }
+            Message::RemovePrintedTax(index) => {
+                if index < state.printed_taxes.len() {
+                    state.printed_taxes.remove(index);
+                }
+            }
+            Message::PrintedServiceChargeChanged(amount) => state.printed_service_charge = amount,
+            Message::PrintedGratuityChanged(amount) => state.printed_gratuity = amount,
+            Message::PrintedTotalChanged(total) => state.printed_total = total,
+
+            Message::TargetChanged(target) => state.target = target,
+            Message::ToleranceChanged(tolerance) => state.tolerance = tolerance,
+            Message::FindTaxedLines => {
+                match state.search(&state.format(catalog)) {
+                    Ok((receipt, target, tolerance)) => {
+                        state.searching = true;
+                        task = Task::perform(async move {
+                            let solutions = solve(&receipt, target, tolerance, SolveLimits::default());
+                            (receipt, solutions)
+                        }, |(receipt, solutions)| Message::TaxedLinesFound(Box::new(receipt), solutions));
+                    }
+                    Err(error) => state.error = Some(error),
+                }
+            }
+            Message::TaxedLinesFound(receipt, solutions) => {
+                state.searching = false;
+                if state.receipt.as_ref() == Some(&*receipt) {
+                    state.solutions = Some(solutions);
+                }
+            }
+            Message::MatchCatalog => {
+                match state.search(&state.format(catalog)) {
+                    Ok((receipt, target, tolerance)) => {
+                        state.searching = true;
+                        let items = catalog.items.to_vec();
+                        task = Task::perform(async move {
+                            let matches = match_catalog(&items, &receipt, target, tolerance, SearchLimits::default());
+                            (receipt, matches)
+                        }, |(receipt, matches)| Message::CatalogMatched(Box::new(receipt), matches));
+                    }
+                    Err(error) => state.error = Some(error),
+                }
+            }
+            Message::CatalogMatched(receipt, matches) => {
+                state.searching = false;
+                if state.receipt.as_ref() == Some(&*receipt) {
+                    state.matches = Some(matches);
+                }
+            }
+            Message::SuggestTaxRate => {
+                match state.search(&state.format(catalog)) {
+                    Ok((receipt, target, tolerance)) => {
+                        state.searching = true;
+                        let taxes = catalog.taxes.to_vec();
+                        task = Task::perform(async move {
+                            let inference = infer_tax_rate(&receipt, target, tolerance, &taxes);
+                            (receipt, inference)
+                        }, |(receipt, inference)| Message::TaxRateSuggested(Box::new(receipt), inference));
+                    }
+                    Err(error) => state.error = Some(error),
+                }
+            }
+            Message::TaxRateSuggested(receipt, inference) => {
+                state.searching = false;
+                if state.receipt.as_ref() == Some(&*receipt) {
+                    if inference.is_none() {
+                        state.error = Some("No tax rate up to 100% reaches that total".to_string());
+                    }
+                    state.inference = inference;
+                }
+            }
+            Message::CreateTax(_) => {}
+        }
+
+        state.refresh(catalog);
+
+        task
+    }
+
+    // The receipt to search with the total it should come to and how far off it may be
+    fn search(&self, format: &MoneyFormat) -> Result<(Receipt, Money, Money), String> {
+        let receipt = match (&self.receipt, &self.build_error) {
+            (Some(receipt), _) => receipt.clone(),
+            (None, Some(error)) => return Err(error.clone()),
+            (None, None) => return Err("Ring up the receipt first".to_string()),
+        };
+        let target = format.parse(&self.target).map_err(|error| format!("The printed total is {}", error))?;
+        let tolerance = if self.tolerance.trim().is_empty() {
+            Money::ZERO
+        } else {
+            format.parse(&self.tolerance).map_err(|error| format!("The tolerance is {}", error))?
+        };
+
+        Ok((receipt, target, tolerance))
+    }
+
+    fn refresh(&mut self, catalog: &Catalog) {
+        let built = self.build(catalog);
+        if built.as_ref().ok() != self.receipt.as_ref() {
+            self.solutions = None;
+            self.inference = None;
+            self.matches = None;
+        }
+
+        match built {
+            Ok(receipt) => {
+                let breakdown = receipt.calculate_total();
+                self.shares = self.split(&receipt);
+                self.reconciliation = self.reconcile(&receipt, &MoneyFormat::new(receipt.currency(), catalog.format.locale));
+                self.home_total = self.rates.as_ref()
+                    .map(|rates| breakdown.home_total(rates).map_err(|error| error.to_string()));
+                self.breakdown = Some(breakdown);
+                self.receipt = Some(receipt);
+                self.build_error = None;
+            }
+            Err(error) => {
+                self.receipt = None;
+                self.breakdown = None;
+                self.shares = None;
+                self.home_total = None;
+                self.reconciliation = None;
+                self.build_error = Some(error);
+            }
+        }
+    }
+
+    // Amounts on the screen are in the receipt's own currency
+    fn format(&self, catalog: &Catalog) -> MoneyFormat {
+        let currency = match &self.receipt {
+            Some(receipt) => receipt.currency(),
+            None => self.currency.unwrap_or(catalog.format.currency),
+        };
+
+        MoneyFormat::new(currency, catalog.format.locale)
+    }
+
+    fn split(&self, receipt: &Receipt) -> Option<Result<Vec<GuestShare>, String>> {
+        let guests = self.split_guests.trim().parse::<usize>().ok()?;
+        if !self.split_by_seat {
+            return Some(split_evenly(receipt, guests).map_err(|error| error.to_string()));
+        }
+
+        // Seats are numbered from 1 on the screen
+        let mut plan = SplitPlan::new(guests, self.lines.len());
+        for (index, line) in self.lines.iter().enumerate() {
+            match line.seat.trim().parse::<usize>() {
+                Ok(seat) if seat >= 1 => plan.assign(index, seat - 1),
+                _ => return Some(Err(format!("{} needs a seat from 1 to {}", line.item.name, guests))),
+            }
+        }
+
+        Some(split(receipt, &plan).map_err(|error| error.to_string()))
+    }
+
+    fn reconcile(&self, receipt: &Receipt, format: &MoneyFormat) -> Option<Result<Reconciliation, String>> {
+        match self.printed(format) {
+            Ok(printed) if printed == PrintedReceipt::default() => None,
+            Ok(printed) => Some(Ok(reconcile(receipt, &printed))),
+            Err(error) => Some(Err(error)),
+        }
+    }
+
+    fn printed(&self, format: &MoneyFormat) -> Result<PrintedReceipt, String> {
+        let parse = |input: &str, figure: &str| -> Result<Option<Money>, String> {
+            if input.trim().is_empty() {
+                return Ok(None);
+            }
+            format.parse(input).map(Some).map_err(|error| format!("The printed {} is {}", figure, error))
+        };
+
+        Ok(PrintedReceipt {
+            subtotal: parse(&self.printed_subtotal, "subtotal")?,
+            taxes: self.printed_taxes.clone(),
+            service_charge: parse(&self.printed_service_charge, "service charge")?,
+            gratuity: parse(&self.printed_gratuity, "gratuity")?,
+            total: parse(&self.printed_total, "total")?,
+        })
+    }
+
+    // The receipt as rung up, with the charges and discounts set up in the other views
+    fn build(&self, catalog: &Catalog) -> Result<Receipt, String> {
+        let mut receipt = Receipt::new(self.receipt_id, format!("Receipt {}", self.receipt_id), catalog.service_charges.to_vec(), catalog.gratuities.to_vec());
+        let currency = self.currency.unwrap_or(catalog.format.currency);
+        receipt.set_currency(currency);
+        receipt.set_rounding_policy(self.rounding);
+        for discount in catalog.discounts {
+            receipt.add_discount(discount.clone());
+        }
+        for (index, line) in self.lines.iter().enumerate() {
+            receipt.add_item(line.item.clone(), line.quantity);
+            for modifier in &line.modifiers {
+                receipt.add_modifier(index, modifier.clone());
+            }
+        }
+
+        if !self.guests.trim().is_empty() {
+            let guests = self.guests.trim().parse::<u32>().map_err(|_| "Guests must be a whole number".to_string())?;
+            receipt.set_guests(guests);
+        }
+        if !self.date.trim().is_empty() {
+            let date = self.date.parse::<Date>().map_err(|error| format!("The date is {}", error))?;
+            receipt.set_date(date);
+        }
+        if !self.minimum_spend.trim().is_empty() {
+            let minimum = MoneyFormat::new(currency, catalog.format.locale).parse(&self.minimum_spend)
+                .map_err(|error| format!("The minimum spend is {}", error))?;
+            let categories = self.minimum_categories.split(',')
+                .map(|category| category.trim().to_string())
+                .filter(|category| !category.is_empty())
+                .collect();
+            // The shortfall is billed untaxed
+            receipt.set_minimum_spend(MinimumSpend::new("F&B Minimum".to_string(), minimum, categories, TaxGroup::new(0, "Untaxed".to_string(), Vec::new())));
+        }
+        for payment in &self.payments {
+            receipt.add_payment(payment.clone());
+        }
+        if !self.exempt_tax_ids.is_empty() {
+            if self.customer.trim().is_empty() || self.certificate.trim().is_empty() {
+                return Err("An exemption needs the customer and their certificate".to_string());
+            }
+            receipt.set_exemption(TaxExemption::new(self.customer.trim().to_string(), self.certificate.trim().to_string(), self.exempt_tax_ids.clone()));
+        }
+
+        Ok(receipt)
+    }
+
+    pub fn view(state: &Self, catalog: &Catalog) -> Element<'static, Message> {
+        column![
+            text("Receipt").size(25),
+            row![
+                scrollable(state.view_inputs(catalog)).width(Length::FillPortion(2)),
+                iced::widget::vertical_rule(1),
+                scrollable(state.view_results(&state.format(catalog))).width(Length::FillPortion(3)),
+            ].spacing(8),
+        ].height(Length::Fill).padding(5).into()
+    }
+
+    fn view_inputs(&self, catalog: &Catalog) -> Element<'static, Message> {
+        let format = self.format(catalog);
+        let item_names: Vec<String> = catalog.items.iter().map(|item| item.name.clone()).collect();
+
+        let mut lines = Column::new().spacing(4);
+        for (index, line) in self.lines.iter().enumerate() {
+            lines = lines.push(row![
+                text(format!("{} {}", line.quantity, line.item.name)).size(14).width(Length::Fill),
+                text_input("Seat", &line.seat).on_input(move |seat| Message::SeatChanged(index, seat)).width(50),
+                button("Add-on").on_press(Message::AddModifier(index)),
+                button("Remove").on_press(Message::RemoveLine(index)),
+            ].spacing(4));
+            for (position, modifier) in line.modifiers.iter().enumerate() {
+                let untaxed = if modifier.exempt_taxes == ExemptTaxes::All { ", untaxed" } else { "" };
+                lines = lines.push(row![
+                    text(format!("    + {} {}{}", modifier.name, format.format(modifier.price), untaxed)).size(12).width(Length::Fill),
+                    button(text("Remove").size(12)).on_press(Message::RemoveModifier(index, position)).padding(2),
+                ].spacing(4));
+            }
+        }
+
+        let mut exempt_taxes = Column::new().spacing(2);
+        for tax in catalog.taxes {
+            let tax_id = tax.id;
+            exempt_taxes = exempt_taxes.push(
+                checkbox(tax.name.clone(), self.exempt_tax_ids.contains(&tax_id)).on_toggle(move |exempt| Message::ExemptTaxToggled(tax_id, exempt))
+            );
+        }
+
+        let mut payments = Column::new().spacing(4);
+        for (index, payment) in self.payments.iter().enumerate() {
+            let tip = if payment.tip.is_zero() { String::new() } else { format!(" + {} tip", format.format(payment.tip)) };
+            payments = payments.push(row![
+                text(format!("{} {}{}", payment.tender, format.format(payment.amount), tip)).size(14).width(Length::Fill),
+                button("Remove").on_press(Message::RemovePayment(index)),
+            ].spacing(4));
+        }
+
+        let mut printed_taxes = Column::new().spacing(4);
+        for (index, tax) in self.printed_taxes.iter().enumerate() {
+            printed_taxes = printed_taxes.push(row![
+                text(format!("{} {}", tax.name, format.format(tax.amount))).size(14).width(Length::Fill),
+                button("Remove").on_press(Message::RemovePrintedTax(index)),
+            ].spacing(4));
+        }
+
+        column![
+            text("Order").size(18),
+            row![
+                pick_list(item_names, self.item_name.clone(), Message::ItemChosen).width(150),
+                text_input("Quantity", &self.quantity).on_input(Message::QuantityChanged).width(70),
+                button("Add").on_press(Message::AddLine),
+            ].spacing(4),
+            row![
+                text_input("Add-on", &self.modifier_name).on_input(Message::ModifierNameChanged).width(120),
+                text_input("Price", &self.modifier_price).on_input(Message::ModifierPriceChanged).width(70),
+                checkbox("Untaxed", self.modifier_untaxed).on_toggle(Message::ModifierUntaxedToggled),
+            ].spacing(4).align_y(iced::Alignment::Center),
+            lines,
+
+            text("Receipt").size(18),
+            text_input("Guests", &self.guests).on_input(Message::GuestsChanged).width(150),
+            text_input("Date, 2026-10-18", &self.date).on_input(Message::DateChanged).width(150),
+            row![
+                pick_list(RoundingMode::ALL, Some(self.rounding.tax.mode), Message::TaxModeChanged).width(110),
+                pick_list(RoundingLevel::ALL, Some(self.rounding.tax.level), Message::TaxLevelChanged).width(110),
+                pick_list(CashRounding::ALL, Some(self.rounding.cash), Message::CashRoundingChanged).width(110),
+            ].spacing(4),
+            row![
+                pick_list(Currency::ALL, Some(format.currency), Message::CurrencyChanged).width(110),
+                text_input("Exchange rates file", &self.rates_path).on_input(Message::RatesPathChanged).width(180),
+                button("Load").on_press(Message::LoadRates),
+            ].spacing(4),
+
+            row![
+                text_input("Minimum spend", &self.minimum_spend).on_input(Message::MinimumSpendChanged).width(110),
+                text_input("Counting categories, e.g. Food, Drinks", &self.minimum_categories).on_input(Message::MinimumCategoriesChanged).width(220),
+            ].spacing(4),
+
+            text("Exemption").size(18),
+            row![
+                text_input("Customer", &self.customer).on_input(Message::CustomerChanged).width(150),
+                text_input("Certificate", &self.certificate).on_input(Message::CertificateChanged).width(150),
+            ].spacing(4),
+            exempt_taxes,
+
+            text("Payments").size(18),
+            row![
+                pick_list(Tender::ALL, Some(self.tender), Message::TenderChosen).width(110),
+                text_input("Amount", &self.payment_amount).on_input(Message::PaymentAmountChanged).width(80),
+                text_input("Tip", &self.payment_tip).on_input(Message::PaymentTipChanged).width(70),
+                button("Pay").on_press(Message::AddPayment),
+            ].spacing(4),
+            payments,
+            button("Close Receipt").on_press(Message::CloseReceipt),
+
+            text("Split").size(18),
+            row![
+                text_input("Guests", &self.split_guests).on_input(Message::SplitGuestsChanged).width(80),
+                checkbox("By seat", self.split_by_seat).on_toggle(Message::SplitBySeatToggled),
+            ].spacing(8).align_y(iced::Alignment::Center),
+
+            text("Printed Receipt").size(18),
+            row![
+                text_input("Subtotal", &self.printed_subtotal).on_input(Message::PrintedSubtotalChanged).width(100),
+                text_input("Total", &self.printed_total).on_input(Message::PrintedTotalChanged).width(100),
+            ].spacing(4),
+            row![
+                text_input("Service charge", &self.printed_service_charge).on_input(Message::PrintedServiceChargeChanged).width(100),
+                text_input("Gratuity", &self.printed_gratuity).on_input(Message::PrintedGratuityChanged).width(100),
+            ].spacing(4),
+            row![
+                text_input("Tax", &self.printed_tax_name).on_input(Message::PrintedTaxNameChanged).width(100),
+                text_input("Amount", &self.printed_tax_amount).on_input(Message::PrintedTaxAmountChanged).width(80),
+                button("Add").on_press(Message::AddPrintedTax),
+            ].spacing(4),
+            printed_taxes,
+
+            text("Work Back From A Total").size(18),
+            row![
+                text_input("Total", &self.target).on_input(Message::TargetChanged).width(100),
+                text_input("Give or take", &self.tolerance).on_input(Message::ToleranceChanged).width(100),
+            ].spacing(4),
+            row![
+                button("Find Taxed Lines").on_press_maybe((!self.searching).then_some(Message::FindTaxedLines)),
+                button("Match Catalog").on_press_maybe((!self.searching).then_some(Message::MatchCatalog)),
+                button("Suggest Tax Rate").on_press_maybe((!self.searching).then_some(Message::SuggestTaxRate)),
+            ].spacing(4),
+        ].spacing(6).padding(5).into()
+    }
+
+    fn view_results(&self, format: &MoneyFormat) -> Element<'static, Message> {
+        let mut results = Column::new().spacing(6).padding(5);
+
+        for error in [&self.error, &self.build_error].into_iter().flatten() {
+            results = results.push(text(error.clone()).color(Color::from_rgb(0.8, 0.1, 0.1)));
+        }
+
+        if let Some(breakdown) = &self.breakdown {
+            results = results.push(text(breakdown.render(format.locale)).font(iced::Font::MONOSPACE).size(14));
+            if !breakdown.shortfall().is_zero() {
+                results = results.push(text(format!("{} short of the minimum spend", format.format(breakdown.shortfall()))));
+            }
+        }
+
+        if let (Some(rates), Some(home_total)) = (&self.rates, &self.home_total) {
+            match home_total {
+                Ok(total) => results = results.push(text(format!("In {}  {}", rates.home, MoneyFormat::for_currency(rates.home).format(*total)))),
+                Err(error) => results = results.push(text(error.clone())),
+            }
+        }
+
+        if let Some(shares) = &self.shares {
+            results = results.push(text("Split").size(18));
+            match shares {
+                Ok(shares) => {
+                    for share in shares {
+                        results = results.push(text(format!("Guest {}  {}", share.guest + 1, format.format(share.total))));
+                    }
+                }
+                Err(error) => results = results.push(text(error.clone())),
+            }
+        }
+
+        if let Some(reconciliation) = &self.reconciliation {
+            results = results.push(text("Printed Receipt").size(18));
+            match reconciliation {
+                Ok(reconciliation) if reconciliation.is_reconciled() => results = results.push(text("Matches the printed receipt")),
+                Ok(reconciliation) => {
+                    for difference in reconciliation.differences() {
+                        let cause = difference.cause.as_ref().map(|cause| format!(", {}", cause)).unwrap_or_default();
+                        results = results.push(text(format!(
+                            "{}  printed {}, worked out {}, off by {}{}",
+                            difference.component, format.format(difference.printed), format.format(difference.calculated), format.format(difference.difference), cause
+                        )));
+                    }
+                }
+                Err(error) => results = results.push(text(error.clone())),
+            }
+        }
+
+        if self.searching {
+            results = results.push(text("Searching..."));
+        }
+
+        if let (Some(receipt), Some(solutions)) = (&self.receipt, &self.solutions) {
+            match solutions {
+                Ok(solutions) => {
+                    results = results.push(text(format!("{} ways to tax the receipt", solutions.len())).size(18));
+                    for solution in solutions {
+                        let untaxed: Vec<String> = receipt.order().iter().zip(&solution.configuration.taxed_lines)
+                            .filter(|(_, taxed)| !**taxed)
+                            .map(|((item, _), _)| item.name.clone())
+                            .chain(receipt.service_charges().iter().zip(&solution.configuration.taxed_service_charges)
+                                .filter(|(_, taxed)| !**taxed)
+                                .map(|(service_charge, _)| service_charge.name.clone()))
+                            .chain(receipt.gratuities().iter().zip(&solution.configuration.taxed_gratuities)
+                                .filter(|(_, taxed)| !**taxed)
+                                .map(|(gratuity, _)| gratuity.name.clone()))
+                            .collect();
+                        let untaxed = if untaxed.is_empty() { "everything taxed".to_string() } else { format!("untaxed: {}", untaxed.join(", ")) };
+                        results = results.push(text(format!("{}  {}", format.format(solution.total), untaxed)));
+                    }
+                }
+                Err(error) => results = results.push(text(error.to_string())),
+            }
+        }
+
+        if let Some(matches) = &self.matches {
+            results = results.push(text(format!("{} orders from the catalog", matches.len())).size(18));
+            for catalog_match in matches {
+                let order: Vec<String> = catalog_match.order.iter()
+                    .map(|(item, quantity)| format!("{} {}", quantity, item.name))
+                    .collect();
+                results = results.push(text(format!("{}  {}", format.format(catalog_match.total), order.join(", "))));
+            }
+        }
+
+        if let Some(inference) = &self.inference {
+            results = results.push(text("Combined Tax Rate").size(18));
+            results = results.push(text(format!("Any rate from {} to {}", inference.lowest, inference.highest)));
+            for tax_match in inference.nearest_taxes.iter().take(3) {
+                results = results.push(text(format!("{} at {}, off by {}", tax_match.tax.name, tax_match.tax.rate, tax_match.distance)));
+            }
+            results = results.push(
+                button(text(format!("Create A Tax At {}", inference.suggested))).on_press(Message::CreateTax(inference.suggested))
+            );
+        }
+
+        if !self.exemptions.is_empty() {
+            results = results.push(text("Exempt Receipts").size(18));
+            for record in &self.exemptions {
+                let date = record.date.map(|date| date.to_string()).unwrap_or_default();
+                results = results.push(text(format!("Receipt {}  {}  {} ({})  {}", record.receipt_id, date, record.customer, record.certificate, format.format(record.total))));
+            }
+        }
+
+        results.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_breakdown_of_the_lines_rung_up() {
+        let items = vec![Item::default()];
+        let catalog = Catalog { items: &items, service_charges: &[], gratuities: &[], discounts: &[], taxes: &[], format: MoneyFormat::default() };
+        let mut state = ReceiptView::new();
+        let _ = ReceiptView::update(&mut state, Message::ItemChosen(items[0].name.clone()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::QuantityChanged("2".to_string()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+
+        let breakdown = state.breakdown.as_ref().unwrap();
+        assert_eq!(breakdown.subtotal, Money::from_cents(2 * 1999));
+
+        let _ = ReceiptView::update(&mut state, Message::DateChanged("18/10/2026".to_string()), &catalog);
+        assert!(state.breakdown.is_none());
+        assert!(state.build_error.is_some());
+    }
+
+    #[test]
+    fn adds_an_add_on_to_its_line() {
+        let items = vec![Item::default()];
+        let catalog = Catalog { items: &items, service_charges: &[], gratuities: &[], discounts: &[], taxes: &[], format: MoneyFormat::default() };
+        let mut state = ReceiptView::new();
+        let _ = ReceiptView::update(&mut state, Message::ItemChosen(items[0].name.clone()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::QuantityChanged("2".to_string()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+        let _ = ReceiptView::update(&mut state, Message::ModifierNameChanged("Add Bacon".to_string()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::ModifierPriceChanged("2.00".to_string()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddModifier(0), &catalog);
+
+        let line = &state.breakdown.as_ref().unwrap().lines[0];
+        assert_eq!(line.modifiers.len(), 1);
+        assert_eq!(line.gross_price(), Money::from_cents(2 * 1999 + 2 * 200));
+    }
+
+    #[test]
+    fn drops_ways_found_for_a_receipt_since_changed() {
+        let items = vec![Item::default()];
+        let catalog = Catalog { items: &items, service_charges: &[], gratuities: &[], discounts: &[], taxes: &[], format: MoneyFormat::default() };
+        let mut state = ReceiptView::new();
+        let _ = ReceiptView::update(&mut state, Message::ItemChosen(items[0].name.clone()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+        let searched = Box::new(state.receipt.clone().unwrap());
+
+        let _ = ReceiptView::update(&mut state, Message::TaxedLinesFound(searched.clone(), Ok(Vec::new())), &catalog);
+        assert!(state.solutions.is_some());
+
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+        assert!(state.solutions.is_none());
+        let _ = ReceiptView::update(&mut state, Message::TaxedLinesFound(searched, Ok(Vec::new())), &catalog);
+        assert!(state.solutions.is_none());
+    }
+
+    #[test]
+    fn splits_lines_by_the_seat_typed() {
+        let items = vec![Item::default()];
+        let catalog = Catalog { items: &items, service_charges: &[], gratuities: &[], discounts: &[], taxes: &[], format: MoneyFormat::default() };
+        let mut state = ReceiptView::new();
+        let _ = ReceiptView::update(&mut state, Message::ItemChosen(items[0].name.clone()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+        let _ = ReceiptView::update(&mut state, Message::SplitGuestsChanged("2".to_string()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::SplitBySeatToggled(true), &catalog);
+        assert!(matches!(state.shares, Some(Err(_))));
+
+        let _ = ReceiptView::update(&mut state, Message::SeatChanged(0, "2".to_string()), &catalog);
+        let shares = state.shares.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(shares[0].total, Money::from_cents(0));
+        assert_eq!(shares[1].total, state.breakdown.as_ref().unwrap().total);
+    }
+
+    #[test]
+    fn closing_an_exempt_receipt_lists_it() {
+        let items = vec![Item::default()];
+        let catalog = Catalog { items: &items, service_charges: &[], gratuities: &[], discounts: &[], taxes: &[], format: MoneyFormat::default() };
+        let tax_ids: Vec<i64> = items[0].tax_group.taxes.iter().map(|tax| tax.id).collect();
+        let mut state = ReceiptView::new();
+        let _ = ReceiptView::update(&mut state, Message::ItemChosen(items[0].name.clone()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+        let _ = ReceiptView::update(&mut state, Message::ExemptTaxToggled(tax_ids[0], true), &catalog);
+        assert!(state.build_error.is_some());
+
+        let _ = ReceiptView::update(&mut state, Message::CustomerChanged("Food Bank".to_string()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::CertificateChanged("EX-42".to_string()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::CloseReceipt, &catalog);
+
+        assert_eq!(state.exemptions.len(), 1);
+        assert_eq!(state.exemptions[0].receipt_id, 1);
+        assert!(state.exemptions[0].total > Money::ZERO);
+        assert_eq!(state.receipt_id, 2);
+        assert!(state.lines.is_empty());
+    }
+
+    #[test]
+    fn rings_up_in_the_currency_picked() {
+        let items = vec![Item::default()];
+        let catalog = Catalog { items: &items, service_charges: &[], gratuities: &[], discounts: &[], taxes: &[], format: MoneyFormat::default() };
+        let mut state = ReceiptView::new();
+        let _ = ReceiptView::update(&mut state, Message::ItemChosen(items[0].name.clone()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+        assert_eq!(state.receipt.as_ref().unwrap().currency(), catalog.format.currency);
+
+        let _ = ReceiptView::update(&mut state, Message::CurrencyChanged(Currency::Gbp), &catalog);
+        assert_eq!(state.receipt.as_ref().unwrap().currency(), Currency::Gbp);
+        assert_eq!(state.format(&catalog).currency, Currency::Gbp);
+        assert_eq!(state.breakdown.as_ref().unwrap().currency, Currency::Gbp);
+    }
+
+    #[test]
+    fn takes_payments_towards_the_receipt() {
+        let items = vec![Item::default()];
+        let catalog = Catalog { items: &items, service_charges: &[], gratuities: &[], discounts: &[], taxes: &[], format: MoneyFormat::default() };
+        let mut state = ReceiptView::new();
+        let _ = ReceiptView::update(&mut state, Message::ItemChosen(items[0].name.clone()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+        let _ = ReceiptView::update(&mut state, Message::TenderChosen(Tender::Card), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::PaymentAmountChanged("10.00".to_string()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::PaymentTipChanged("2.00".to_string()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddPayment, &catalog);
+
+        assert_eq!(state.receipt.as_ref().unwrap().payments().len(), 1);
+        assert_eq!(state.breakdown.as_ref().unwrap().payments[0].tendered(), Money::from_cents(1200));
+
+        let _ = ReceiptView::update(&mut state, Message::RemovePayment(0), &catalog);
+        assert!(state.receipt.as_ref().unwrap().payments().is_empty());
+    }
+
+    #[test]
+    fn compares_the_printed_figures_typed() {
+        let items = vec![Item::default()];
+        let catalog = Catalog { items: &items, service_charges: &[], gratuities: &[], discounts: &[], taxes: &[], format: MoneyFormat::default() };
+        let mut state = ReceiptView::new();
+        let _ = ReceiptView::update(&mut state, Message::ItemChosen(items[0].name.clone()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+        assert!(state.reconciliation.is_none());
+
+        let _ = ReceiptView::update(&mut state, Message::PrintedSubtotalChanged("19.99".to_string()), &catalog);
+        assert!(state.reconciliation.as_ref().unwrap().as_ref().unwrap().is_reconciled());
+
+        let _ = ReceiptView::update(&mut state, Message::PrintedSubtotalChanged("20.99".to_string()), &catalog);
+        let reconciliation = state.reconciliation.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(reconciliation.differences()[0].difference, Money::from_cents(100));
+    }
+
+    #[test]
+    fn bills_the_shortfall_of_a_minimum_spend() {
+        let items = vec![Item::default()];
+        let catalog = Catalog { items: &items, service_charges: &[], gratuities: &[], discounts: &[], taxes: &[], format: MoneyFormat::default() };
+        let mut state = ReceiptView::new();
+        let _ = ReceiptView::update(&mut state, Message::ItemChosen(items[0].name.clone()), &catalog);
+        let _ = ReceiptView::update(&mut state, Message::AddLine, &catalog);
+        let _ = ReceiptView::update(&mut state, Message::MinimumSpendChanged("50.00".to_string()), &catalog);
+        assert_eq!(state.breakdown.as_ref().unwrap().shortfall(), Money::from_cents(5000 - 1999));
+
+        let _ = ReceiptView::update(&mut state, Message::MinimumCategoriesChanged("Drinks".to_string()), &catalog);
+        assert_eq!(state.breakdown.as_ref().unwrap().shortfall(), Money::from_cents(5000));
+    }
+}
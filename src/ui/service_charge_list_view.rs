@@ -1,6 +1,9 @@
 use iced::{Element, Length, Task};
 use iced::widget::{column, text};
 
+use crate::core::exemption::ExemptTaxes;
+use crate::core::charge::ChargeValue;
+use crate::core::currency::MoneyFormat;
 use crate::core::service_charge::{ServiceCharge, create_service_charge_table};
 
 #[derive(Debug, Clone)]
@@ -32,7 +35,11 @@ impl ServiceChargeView {
         }
     }
 
-    pub fn update(state: &mut Self, message: Message) -> Task<Message> {
+    pub fn service_charges(&self) -> &[ServiceCharge] {
+        &self.service_charges
+    }
+
+    pub fn update(state: &mut Self, message: Message, format: MoneyFormat) -> Task<Message> {
         match message {
             //modify service_charge list and states
             Message::DeleteServiceCharge(service_charge_id) => {
@@ -45,8 +52,14 @@ impl ServiceChargeView {
                 if let Some(service_charge) = state.service_charges.iter_mut().find(|i| i.id == service_charge_id) {
                     if let Some(edit_state) = state.edit_states.get(&service_charge_id) {
                         service_charge.name = edit_state.name.clone();
-                        if let Ok(percent) = edit_state.percent.parse::<f64>() {
-                            service_charge.percent = percent;
+                        if let Ok(exempt_taxes) = edit_state.exempt_taxes.parse::<ExemptTaxes>() {
+                            service_charge.exempt_taxes = exempt_taxes;
+                        }
+                        if let Ok(value) = ChargeValue::parse(&edit_state.value, &format) {
+                            service_charge.value = value;
+                        }
+                        if let Ok(order) = edit_state.order.parse::<i32>() {
+                            service_charge.order = order;
                         }
                     }
                 }
@@ -64,8 +77,9 @@ impl ServiceChargeView {
                     if let Some(service_charge) = state.service_charges.iter().find(|i| i.id == service_charge_id) {
                         state.edit_states.insert(service_charge_id, ServiceChargeEditState {
                             name: service_charge.name.clone(),
-                            percent: service_charge.percent.to_string(),
-                            tax_overide: service_charge.tax_overide,
+                            value: service_charge.value.describe(&format),
+                            order: service_charge.order.to_string(),
+                            exempt_taxes: service_charge.exempt_taxes.to_string(),
                             is_editing: true,
                         });
                     } 
@@ -79,7 +93,9 @@ impl ServiceChargeView {
                 if let Some(edit_state) = state.edit_states.get_mut(&service_charge_id) {
                     match field_name.as_str() {
                         "name" => edit_state.name = new_value,
-                        "percent" => edit_state.percent = new_value,
+                        "value" => edit_state.value = new_value,
+                        "order" => edit_state.order = new_value,
+                        "exempt_taxes" => edit_state.exempt_taxes = new_value,
                         _ => {}
                     }
                 }
@@ -89,11 +105,11 @@ impl ServiceChargeView {
         }
     }
 
-    pub fn view<'a>(state: &Self) -> Element<'static, Message>{
+    pub fn view<'a>(state: &Self, format: MoneyFormat) -> Element<'static, Message>{
 
         column![
             text("Service Charges").size(25),
-            create_service_charge_table(state.service_charges.clone(), &mut state.edit_states.clone())
+            create_service_charge_table(state.service_charges.clone(), format, &mut state.edit_states.clone())
         ].height(Length::Fill).padding(5).into()
     }
 }
@@ -101,7 +117,8 @@ impl ServiceChargeView {
 #[derive(Debug, Clone)]
 pub struct ServiceChargeEditState {
     pub name: String,
-    pub percent: String,
-    pub tax_overide: bool,
+    pub value: String,
+    pub order: String,
+    pub exempt_taxes: String,
     pub is_editing: bool,
 }
\ No newline at end of file
@@ -2,7 +2,9 @@ use iced::{Element, Length, Task};
 use iced::widget::column;
 
 use crate::core::tax_group::{TaxGroup, create_tax_group_table};
-use crate::core::tax::Tax;
+use crate::core::rounding::{GroupRounding, TaxRounding};
+use crate::core::tax::{Tax, TaxBase};
+use crate::ui::add_tax_group_view::TaxFields;
 
 #[derive(Debug, Clone)]
 pub struct TaxGroupView {
@@ -17,8 +19,11 @@ pub enum Message {
     NewTaxGroup(TaxGroup),
     ToggleEditMode(i64, bool),
     EditField(i64, String, String),
-    EditTaxes(i64, Vec<Tax>),
-    TaxPressed(i64),
+    AddTax(i64),
+    RemoveTax(i64, usize),
+    EditTax(i64, usize, String, String),
+    TaxBaseChanged(i64, usize, TaxBase),
+    RoundingChanged(i64, GroupRounding),
 }
 
 impl TaxGroupView {
@@ -44,9 +49,21 @@ impl TaxGroupView {
             }
             Message::SaveTaxGroup(tax_group_id) => {
                 if let Some(tax_group) = state.tax_groups.iter_mut().find(|i| i.id == tax_group_id) {
-                    if let Some(edit_state) = state.edit_states.get(&tax_group_id) {
+                    if let Some(edit_state) = state.edit_states.get_mut(&tax_group_id) {
+                        // Stay in edit mode until every tax reads correctly
+                        let taxes: Result<Vec<Tax>, String> = edit_state.taxes.iter()
+                            .enumerate()
+                            .map(|(index, fields)| fields.to_tax(index + 1))
+                            .collect();
+                        match taxes {
+                            Ok(taxes) => tax_group.taxes = taxes,
+                            Err(error) => {
+                                edit_state.error = Some(error);
+                                return Task::none();
+                            }
+                        }
                         tax_group.name = edit_state.name.clone();
-                        tax_group.taxes = edit_state.taxes.clone();
+                        tax_group.rounding = edit_state.rounding;
                     }
                 }
 
@@ -62,7 +79,9 @@ impl TaxGroupView {
                     if let Some(tax_group) = state.tax_groups.iter().find(|i| i.id == tax_group_id) {
                         state.edit_states.insert(tax_group_id, TaxGroupEditState {
                             name: tax_group.name.clone(),
-                            taxes: tax_group.taxes.clone(),
+                            taxes: tax_group.taxes.iter().map(TaxFields::from_tax).collect(),
+                            rounding: tax_group.rounding,
+                            error: None,
                             is_editing: true,
                         });
                     } 
@@ -82,22 +101,46 @@ impl TaxGroupView {
 
                 Task::none()
             }
-            Message::EditTaxes(tax_group_id, new_taxes) => {
+            Message::AddTax(tax_group_id) => {
                 if let Some(edit_state) = state.edit_states.get_mut(&tax_group_id) {
-                    edit_state.taxes = new_taxes;
+                    edit_state.taxes.push(TaxFields::default());
                 }
 
                 Task::none()
             }
-            Message::TaxPressed(tax_group_id) => {
-/*                 if let Some(edit_state) = state.edit_states.get_mut(&tax_group_id) {
+            Message::RemoveTax(tax_group_id, index) => {
+                if let Some(edit_state) = state.edit_states.get_mut(&tax_group_id) {
+                    if index < edit_state.taxes.len() {
+                        edit_state.taxes.remove(index);
+                    }
+                }
+
+                Task::none()
+            }
+            Message::EditTax(tax_group_id, index, field_name, new_value) => {
+                if let Some(fields) = state.edit_states.get_mut(&tax_group_id).and_then(|edit_state| edit_state.taxes.get_mut(index)) {
                     match field_name.as_str() {
-                        "taxes" => {
-                            
-                        }
+                        "id" => fields.id = new_value,
+                        "name" => fields.name = new_value,
+                        "rate" => fields.rate = new_value,
+                        "order" => fields.order = new_value,
                         _ => {}
                     }
-                } */
+                }
+
+                Task::none()
+            }
+            Message::TaxBaseChanged(tax_group_id, index, base) => {
+                if let Some(fields) = state.edit_states.get_mut(&tax_group_id).and_then(|edit_state| edit_state.taxes.get_mut(index)) {
+                    fields.base = base;
+                }
+
+                Task::none()
+            }
+            Message::RoundingChanged(tax_group_id, rounding) => {
+                if let Some(edit_state) = state.edit_states.get_mut(&tax_group_id) {
+                    edit_state.rounding = rounding.rounding();
+                }
 
                 Task::none()
             }
@@ -115,6 +158,9 @@ impl TaxGroupView {
 #[derive(Debug, Clone)]
 pub struct TaxGroupEditState {
     pub name: String,
-    pub taxes: Vec<Tax>,
+    pub taxes: Vec<TaxFields>,
+    pub rounding: Option<TaxRounding>,
+    // Why the last save was turned down
+    pub error: Option<String>,
     pub is_editing: bool,
 }
\ No newline at end of file
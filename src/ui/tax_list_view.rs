@@ -1,12 +1,16 @@
-use iced::{Element, Length, Task};
-use iced::widget::column;
+use iced::{Color, Element, Length, Task};
+use iced::widget::{column, text};
 
-use crate::core::tax::{Tax, create_taxes_table};
+use crate::core::date::Date;
+use crate::core::money::Rate;
+use crate::core::tax::{Tax, create_rate_history, create_taxes_table};
 
 #[derive(Debug, Clone)]
 pub struct TaxView {
     taxes: Vec<Tax>,
     edit_states: std::collections::HashMap<i64, TaxEditState>,
+    // The tax whose past rates are shown under the table
+    history_shown: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -16,7 +20,8 @@ pub enum Message {
     SaveTax(i64),
     NewTax(Tax),
     ToggleEditMode(i64, bool),
-    EditField(i64, String, String)
+    EditField(i64, String, String),
+    ShowHistory(i64),
 }
 
 impl TaxView {
@@ -28,9 +33,14 @@ impl TaxView {
         TaxView {
             taxes: tax_vec,
             edit_states: std::collections::HashMap::new(),
+            history_shown: None,
         }
     }
 
+    pub fn taxes(&self) -> &[Tax] {
+        &self.taxes
+    }
+
     pub fn update(state: &mut Self, message: Message) -> Task<Message> {
         match message {
             //modify tax list and states
@@ -42,10 +52,27 @@ impl TaxView {
             }
             Message::SaveTax(tax_id) => {
                 if let Some(tax) = state.taxes.iter_mut().find(|i| i.id == tax_id) {
-                    if let Some(edit_state) = state.edit_states.get(&tax_id) {
+                    if let Some(edit_state) = state.edit_states.get_mut(&tax_id) {
+                        // Stay in edit mode until the rate and its first day both read
+                        let percent = match edit_state.percent.parse::<Rate>() {
+                            Ok(percent) => percent,
+                            Err(error) => {
+                                edit_state.error = Some(format!("Rate: {}", error));
+                                return Task::none();
+                            }
+                        };
+                        let from = match edit_state.from.parse::<Date>() {
+                            Ok(from) => from,
+                            Err(error) => {
+                                edit_state.error = Some(format!("Effective from: {}", error));
+                                return Task::none();
+                            }
+                        };
+
                         tax.name = edit_state.name.clone();
-                        if let Ok(percent) = edit_state.percent.parse::<f64>() {
-                            tax.percent = percent;
+                        // The old rate stays in the history up to the day before
+                        if percent != tax.rate_on(from) {
+                            tax.set_rate(percent, from);
                         }
                     }
                 }
@@ -63,7 +90,9 @@ impl TaxView {
                     if let Some(tax) = state.taxes.iter().find(|i| i.id == tax_id) {
                         state.edit_states.insert(tax_id, TaxEditState {
                             name: tax.name.clone(),
-                            percent: tax.percent.to_string(),
+                            percent: tax.rate.to_string(),
+                            from: Date::today().to_string(),
+                            error: None,
                             is_editing: true,
                         });
                     } 
@@ -78,10 +107,16 @@ impl TaxView {
                     match field_name.as_str() {
                         "name" => edit_state.name = new_value,
                         "percent" => edit_state.percent = new_value,
+                        "from" => edit_state.from = new_value,
                         _ => {}
                     }
                 }
 
+                Task::none()
+            }
+            Message::ShowHistory(tax_id) => {
+                state.history_shown = if state.history_shown == Some(tax_id) { None } else { Some(tax_id) };
+
                 Task::none()
             }
         }
@@ -89,9 +124,17 @@ impl TaxView {
 
     pub fn view<'a>(state: &Self) -> Element<'static, Message>{
 
-        column![
+        let mut content = column![
             create_taxes_table(state.taxes.clone(), &mut state.edit_states.clone())
-        ].height(Length::Fill).into()
+        ];
+        if let Some(tax) = state.history_shown.and_then(|tax_id| state.taxes.iter().find(|tax| tax.id == tax_id)) {
+            content = content.push(create_rate_history(tax));
+        }
+        for error in state.edit_states.values().filter_map(|edit_state| edit_state.error.clone()) {
+            content = content.push(text(error).color(Color::from_rgb(0.8, 0.1, 0.1)).size(12));
+        }
+
+        content.height(Length::Fill).into()
     }
 }
 
@@ -99,5 +142,42 @@ impl TaxView {
 pub struct TaxEditState {
     pub name: String,
     pub percent: String,
+    // First day of the edited rate, today unless changed
+    pub from: String,
+    // Why the last save was turned down
+    pub error: Option<String>,
     pub is_editing: bool,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(state: &mut TaxView, field_name: &str, value: &str) {
+        let _ = TaxView::update(state, Message::EditField(1, field_name.to_string(), value.to_string()));
+    }
+
+    #[test]
+    fn starts_a_new_rate_on_the_effective_from_date() {
+        let mut state = TaxView::new();
+        let _ = TaxView::update(&mut state, Message::ToggleEditMode(1, true));
+        edit(&mut state, "percent", "8.25");
+        edit(&mut state, "from", "2024-07-01");
+        let _ = TaxView::update(&mut state, Message::SaveTax(1));
+
+        let tax = &state.taxes[0];
+        assert_eq!(tax.rate, "8.25".parse().unwrap());
+        assert_eq!(tax.current_from(), Some(Date::new(2024, 7, 1).unwrap()));
+        assert_eq!(tax.rate_on(Date::new(2024, 6, 30).unwrap()), Rate::from_basis_points(775));
+    }
+
+    #[test]
+    fn stays_in_edit_mode_when_the_date_does_not_read() {
+        let mut state = TaxView::new();
+        let _ = TaxView::update(&mut state, Message::ToggleEditMode(1, true));
+        edit(&mut state, "from", "July 1st");
+        let _ = TaxView::update(&mut state, Message::SaveTax(1));
+
+        assert!(state.edit_states[&1].error.is_some());
+        assert!(state.taxes[0].history.is_empty());
+    }
+}